@@ -19,3 +19,222 @@ Feature: Interactions
       | foobaz |   |
     When I read the last chunk
     Then the chunk I read should equal "foobar"
+
+  Scenario: Read chunk with an adjusted timeout
+    Given an Interaction with a 50 millisecond timeout
+      | first  | 2 |
+      | second |   |
+    When I set the timeout to 3 timeouts
+    And I read a chunk
+    Then the chunk I read should equal "firstsecond"
+    And the timeout should be 150 milliseconds
+
+  Scenario: Read chunk with a temporary timeout
+    Given an Interaction with a 50 millisecond timeout
+      | first  | 2 |
+      | second |   |
+    When I temporarily set the timeout to 3 timeouts
+    Then the chunk I read should equal "firstsecond"
+    And the timeout should be 50 milliseconds
+
+  Scenario: Read until a delimiter
+    Given an Interaction with a 50 millisecond timeout
+      | Name: | 3 |
+      | Your choice (1-4): | |
+      | 2 | |
+    When I read until "(1-4):"
+    Then the chunk I read should equal "Name:Your choice (1-4):"
+    When I read exactly 1 bytes
+    Then the chunk I read should equal "2"
+
+  Scenario: Read a line
+    Given an Interaction with a 50 millisecond timeout
+      | first\nsecond\n | |
+    When I read a line
+    Then the chunk I read should equal "first\n"
+
+  Scenario: Read until a delimiter that never arrives
+    Given an Interaction with a 50 millisecond timeout
+      | > | |
+    When I read until "$ " within 2 timeouts
+    Then the read should time out
+    And the chunk I read should equal ">"
+
+  Scenario: Read a chunk of raw bytes
+    Given an Interaction with a 50 millisecond timeout
+    And the remote sends the hex bytes "efbeaddeff7f0000"
+    When I read a chunk of bytes
+    Then the bytes I read should equal the hex bytes "efbeaddeff7f0000"
+
+  Scenario: Forward raw bytes over a channel
+    Given an Interaction with a 50 millisecond timeout
+    And the remote sends the hex bytes "4c65616b3a20f0a1e4f7ff7f0a"
+    When I run the Interaction over a byte channel until the remote closes
+    Then the bytes I read should equal the hex bytes "4c65616b3a20f0a1e4f7ff7f0a"
+
+  Scenario: Send after a prompt
+    Given an Interaction with a 50 millisecond timeout
+      | Menu\n1. Leak\n> Name: | |
+    When I send "1" after "> "
+    Then the chunk I read should equal "Menu\n1. Leak\n"
+    And the remote should receive "1"
+    When I send the line "AAAA" after "Name:"
+    Then the chunk I read should equal ""
+    And the remote should receive "AAAA\n"
+    When I send the line "BBBB"
+    Then the remote should receive "BBBB\n"
+
+  Scenario: Run a script
+    Given an Interaction with a 50 millisecond timeout
+      | Name: | 1 |
+      | Hello, AAAA! Your ticket is 0x1337.\nChoice: | |
+    When I run the script
+      """
+      # Register, then pick option 2
+      expect Name:
+      sendline AAAA
+      capture ticket ticket is 0x([0-9a-f]+)\.
+      sleep 10ms
+      expect Choice:$
+      send 2\x00
+      """
+    Then the capture "ticket" should equal "1337"
+    And the remote should receive "AAAA\n2"
+
+  Scenario: Compute responses from earlier output
+    Given an Interaction with a 50 millisecond timeout
+      | Leak: 0x1000\n> | 3 |
+      | Leak: 0x2000\n> | 3 |
+      | Bye             |   |
+    When I run the Interaction, answering each leak with its value plus 16
+    Then the remote should receive "0x1010\n0x2010\n"
+
+  Scenario: Echo escaped output to a log
+    Given an Interaction with a 50 millisecond timeout
+      | Leak: 0x1000\n> | 3 |
+      | Bye             |   |
+    When I run the Interaction, answering each leak with its value plus 16, echoing escaped output to a log
+    Then the log should read
+      """
+      << Leak: 0x1000\n>
+      >> 0x1010\n
+      << Bye
+      """
+
+  Scenario: Echo a hexdump to a log
+    Given an Interaction with a 50 millisecond timeout
+      | Leak: 0x1000, a canary and some padding\n> | 3 |
+      | Bye                                        |   |
+    When I run the Interaction, answering each leak with its value plus 16, echoing hexdump output to a log
+    Then the log should read
+      """
+      << 41 bytes
+      00000000  4c 65 61 6b 3a 20 30 78  31 30 30 30 2c 20 61 20  |Leak: 0x1000, a |
+      00000010  63 61 6e 61 72 79 20 61  6e 64 20 73 6f 6d 65 20  |canary and some |
+      00000020  70 61 64 64 69 6e 67 0a  3e                       |padding.>|
+      >> 7 bytes
+      00000000  30 78 31 30 31 30 0a                              |0x1010.|
+      << 3 bytes
+      00000000  42 79 65                                          |Bye|
+      """
+
+  Scenario: Expect one of several patterns
+    Given an Interaction with a 50 millisecond timeout
+      | Welcome!\n               | 1 |
+      | Error: no such user\nbye |   |
+    When I expect one of
+      | Your choice:  |
+      | Error: (.*)\n |
+    Then pattern 1 should have matched
+    And capture group 1 should equal "no such user"
+    And the data before the match should equal "Welcome!\n"
+    When I read the last chunk
+    Then the chunk I read should equal "bye"
+
+  Scenario: Expect a pattern after a large amount of data
+    Given an Interaction with a 50 millisecond timeout
+    And the remote sends 100000 bytes of "A"
+    And the remote sends the hex bytes "4e616d653a"
+    When I expect one of within 20 timeouts
+      | [A-Z][a-z]+[^A-Za-z] |
+    Then pattern 0 should have matched
+    And capture group 0 should equal "Name:"
+
+  Scenario: Expect a pattern built with flags
+    Given an Interaction with a 50 millisecond timeout
+      | WELCOME\n | |
+    When I expect one of ignoring case
+      | welcome |
+    Then pattern 0 should have matched
+    And capture group 0 should equal "WELCOME"
+
+  Scenario: Expect a pattern that never arrives
+    Given an Interaction with a 50 millisecond timeout
+      | Your choice: | |
+    When I expect one of within 2 timeouts
+      | [$] |
+    Then the read should time out
+    And the chunk I read should equal "Your choice:"
+
+  Scenario: Record a transcript
+    Given a recorded Interaction with a 50 millisecond timeout
+      | Welcome!\n> | 1 |
+      | Bye\n       |   |
+    When I send the line "1" after ">" through the recorded Interaction
+    And I read a chunk from the recorded Interaction
+    Then the chunk I read should equal "Bye\n"
+    When I close the recorded Interaction
+    Then the transcript should contain
+      | received | Welcome!\n> |
+      | sent     | 1\n         |
+      | received | Bye\n       |
+
+  Scenario: Replay a transcript
+    Given the transcript
+      """
+      {"direction":"received","time":0.000000,"data":"57656c636f6d65210a"}
+      {"direction":"received","time":0.020000,"data":"3e20"}
+      {"direction":"sent","time":0.100000,"data":"310a"}
+      {"direction":"received","time":0.200000,"data":"427965"}
+      """
+    When I read a chunk from the replay
+    Then the chunk I read should equal "Welcome!\n> "
+    When I send the line "1" to the replay
+    And I read a chunk from the replay
+    Then the chunk I read should equal "Bye"
+    And the replay should close cleanly
+
+  Scenario: Replay rejects unexpected writes
+    Given the transcript
+      """
+      {"direction":"received","time":0.000000,"data":"57656c636f6d65210a"}
+      {"direction":"received","time":0.200000,"data":"3e20"}
+      {"direction":"sent","time":0.300000,"data":"310a"}
+      """
+    When I read a chunk from the replay
+    Then the chunk I read should equal "Welcome!\n"
+    When I send the line "2" to the replay
+    Then the replay should reject the write
+    And the replay should not close cleanly
+
+  Rule: Buffered interactions
+
+    Scenario: Peek and unread
+      Given a buffered Interaction with a 50 millisecond timeout
+        | world | |
+      When I peek at the buffered Interaction
+      Then the chunk I read should equal "world"
+      And the buffered Interaction should have 5 bytes pending
+      When I unread "hello "
+      And I read 11 bytes from the buffered Interaction
+      Then the chunk I read should equal "hello world"
+      And the buffered Interaction should have 0 bytes pending
+
+    Scenario: Clean
+      Given a buffered Interaction with a 50 millisecond timeout
+        | stale | .5 |
+        | data  |    |
+      When I peek at the buffered Interaction
+      And I clean the buffered Interaction
+      Then the chunk I read should equal "staledata"
+      And the buffered Interaction should have 0 bytes pending
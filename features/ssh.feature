@@ -0,0 +1,133 @@
+Feature: SSH interactions
+  In order to exploit targets that are only reachable through SSH
+  As a hacker
+  I want to interact with remote processes over an SSH session
+
+  Scenario: Connect with a custom port, user and identity
+    Given an SSH server
+    When I connect to the SSH server
+    And I spawn "cat" over the SSH connection
+    And I send the line "hello" to SSH process 1
+    Then SSH process 1 should receive the line "hello"
+
+  Scenario: Reject an unknown host key
+    Given an SSH server
+    When I connect to the SSH server, checking its host key
+    Then the SSH connection should fail
+
+  Scenario: Reject an unauthorized identity
+    Given an SSH server
+    When I connect to the SSH server with another identity
+    Then the SSH connection should fail
+
+  Scenario: Connect through a jump host
+    Given an SSH server
+    When I connect to the SSH server through itself as a jump host
+    And I spawn "cat" over the SSH connection
+    And I send the line "jumped" to SSH process 1
+    Then SSH process 1 should receive the line "jumped"
+    When I connect to the SSH server through a jump host that is not listening
+    Then the SSH connection should fail
+
+  Scenario: Skip the remote shell's own output
+    Given an SSH server that prints "Welcome to the challenge box" on login
+    When I connect to the SSH server
+    And I spawn "echo ready" over the SSH connection
+    Then SSH process 1 should receive the line "ready"
+
+  Scenario: Run a command containing quotes
+    Given an SSH server
+    When I connect to the SSH server
+    And I spawn "GREETING='hello there' sh -c 'echo $GREETING'" over the SSH connection
+    Then SSH process 1 should receive the line "hello there"
+
+  Scenario: Run several processes and commands over one session
+    Given an SSH server
+    When I connect to the SSH server
+    And I spawn "cat" over the SSH connection
+    And I spawn "cat" over the SSH connection
+    And I send the line "second" to SSH process 2
+    And I send the line "first" to SSH process 1
+    Then SSH process 1 should receive the line "first"
+    And SSH process 2 should receive the line "second"
+    When I run "echo one-off" over the SSH connection
+    Then the command should print "one-off"
+    When I close SSH process 1
+    And I run "echo still connected" over the SSH connection
+    Then the command should print "still connected"
+    And SSH process 2 should still echo lines
+
+  Scenario: Run processes in a pseudo-terminal
+    Given an SSH server
+    When I connect to the SSH server
+    And I spawn "test -t 0 && test -t 1 && echo terminal || echo pipe" over the SSH connection
+    Then SSH process 1 should receive the line "pipe"
+    When I spawn "test -t 0 && test -t 1 && echo terminal || echo pipe" over the SSH connection in a raw pseudo-terminal
+    Then SSH process 2 should receive the line "terminal"
+    When I spawn "cat" over the SSH connection in a raw pseudo-terminal
+    And I send the line "hello" to SSH process 3
+    Then SSH process 3 should receive the line "hello"
+
+  Scenario: Track the PID of a process that replaces the shell
+    Given an SSH server
+    When I connect to the SSH server
+    And I spawn "exec sleep 60" over the SSH connection
+    Then the PID of SSH process 1 should belong to "sleep"
+    When I close SSH process 1
+    Then the closed process should no longer be running
+
+  Scenario: Track the PID of a process by name
+    Given an SSH server
+    When I connect to the SSH server
+    And I spawn "LIBSPL=1 sleep 60" over the SSH connection
+    And I name SSH process 1 "sleep"
+    Then the PID of SSH process 1 should belong to "sleep"
+    When I close SSH process 1
+    Then the closed process should no longer be running
+
+  Scenario: Track the PID of a process started by a login shell that does not exec
+    Given an SSH server that prints "Welcome to the challenge box" on login
+    When I connect to the SSH server
+    And I spawn "LIBSPL=1 sleep 60" over the SSH connection
+    And I name SSH process 1 "sleep"
+    Then the PID of SSH process 1 should belong to "sleep"
+    When I close SSH process 1
+    Then the closed process should no longer be running
+
+  Scenario: Close every process of a command
+    Given an SSH server that prints "Welcome to the challenge box" on login
+    When I connect to the SSH server
+    And I spawn "sleep 61 | sleep 62" over the SSH connection
+    And I close SSH process 1
+    Then no process of the closed process group should be running
+
+  Scenario: Upload and download files with their permissions
+    Given an SSH server
+    And a local file "exploit" containing "#!/bin/sh" with mode 751
+    When I connect to the SSH server
+    And I upload "exploit" to "uploaded"
+    Then the file "uploaded" should contain "#!/bin/sh" with mode 751
+    When I download "uploaded" to "downloaded"
+    Then the file "downloaded" should contain "#!/bin/sh" with mode 751
+
+  Scenario: Report why a download failed
+    Given an SSH server
+    When I connect to the SSH server
+    And I download "missing" to "downloaded"
+    Then the transfer should fail with "No such file"
+
+  Scenario: Report a missing stat when downloading
+    Given an SSH server without "stat"
+    And a local file "flag" containing "flag{}" with mode 600
+    When I connect to the SSH server
+    And I download "flag" to "downloaded"
+    Then the transfer should fail with "cannot read the mode of"
+
+  Scenario: Forward a connection to a service behind the SSH server
+    Given an SSH server
+    And a TCP service that sends "hello from behind"
+    When I connect to the SSH server
+    And I forward a connection to the TCP service
+    Then the forwarded connection should receive the line "hello from behind"
+    When I close the forwarded connection
+    Then the forward should no longer accept connections
@@ -0,0 +1,134 @@
+Feature: Local process interactions
+  In order to exploit local binaries
+  As a hacker
+  I want to interact with child processes
+
+  Scenario: Pipes are not terminals
+    Given the process "sh -c 'test -t 0 && test -t 1 && echo terminal || echo pipe'"
+    Then the process should print the line "pipe"
+
+  Scenario: Run a process in a pseudo-terminal
+    Given the process "sh -c 'test -t 0 && test -t 1 && test -t 2 && echo terminal || echo pipe'" in a raw pseudo-terminal
+    Then the process should print the line "terminal"
+
+  Scenario: Raw pseudo-terminals pass data through unmodified
+    Given the process "cat" in a raw pseudo-terminal
+    When I send the line "a\tb" to the process
+    Then the process should print the line "a\tb"
+
+  Scenario: Cooked pseudo-terminals echo input
+    Given the process "cat" in a cooked pseudo-terminal
+    When I send the line "hello" to the process
+    Then the process should print the line "hello\r"
+    And the process should print the line "hello\r"
+
+  Scenario: Merge stderr into the main stream
+    Given the process "sh -c 'echo out; echo err >&2; echo more'" with merged stderr
+    Then the process should print the line "out"
+    And the process should print the line "err"
+    And the process should print the line "more"
+    And the process should have no stderr handle
+
+  Scenario: Merge stderr into a pseudo-terminal
+    Given the process "sh -c 'echo err >&2'" in a raw pseudo-terminal with merged stderr
+    Then the process should print the line "err"
+
+  Scenario: Read stderr separately
+    Given the process "sh -c 'echo out; echo err >&2'" with separate stderr
+    Then the process should print the line "out"
+    And the process should print "err\n" on stderr
+
+  Scenario: Discard stderr
+    Given the process "sh -c 'echo err >&2; echo out'" with discarded stderr
+    Then the process should print the line "out"
+    And the process should have no stderr handle
+
+  Scenario: Set environment variables
+    Given the executable "sh -c 'echo $FOO'"
+    And the environment variable "FOO" set to "bar"
+    When I launch the process
+    Then the process should print the line "bar"
+
+  Scenario: Remove environment variables
+    Given the executable "sh -c 'echo ${HOME-unset}'"
+    And the environment variable "HOME" removed
+    When I launch the process
+    Then the process should print the line "unset"
+
+  Scenario: Clear the environment
+    Given the executable "/usr/bin/env"
+    And a cleared environment
+    And the environment variable "ONLY" set to "this"
+    When I launch the process
+    Then the process should print the line "ONLY=this"
+
+  Scenario: Preload libraries
+    Given the executable "sh -c 'echo $LD_PRELOAD'"
+    And the preloaded library "/nonexistent/a.so"
+    And the preloaded library "/nonexistent/b.so"
+    And discarded stderr
+    When I launch the process
+    Then the process should print the line "/nonexistent/a.so:/nonexistent/b.so"
+
+  Scenario: Set the working directory
+    Given the executable "pwd"
+    And the working directory "/"
+    When I launch the process
+    Then the process should print the line "/"
+
+  Scenario: Override argv[0]
+    Given the executable "sh -c 'echo $0'"
+    And argv[0] set to "pwn"
+    When I launch the process
+    Then the process should print the line "pwn"
+
+  Scenario: Set the umask
+    Given the executable "sh -c umask"
+    And the umask 027
+    When I launch the process
+    Then the process should print the line "0027"
+
+  Scenario: Pass on extra file descriptors
+    Given the executable "sh -c 'head -n 1 <&3'"
+    And file descriptor 3 open on "features/stdio.feature"
+    When I launch the process
+    Then the process should print the line "Feature: Local process interactions"
+
+  Scenario: Pass on file descriptors in each other's place
+    Given the executable "sh -c 'head -n 1 /proc/self/fd/$FIRST; head -n 1 /proc/self/fd/$SECOND'"
+    And "features/stdio.feature" and "Cargo.toml" passed on in each other's file descriptors
+    When I launch the process
+    Then the process should print the line "Feature: Local process interactions"
+    And the process should print the line "[package]"
+
+  Scenario: Run through a bundled loader
+    Given the executable "/usr/bin/readlink /proc/self/exe"
+    And the system's libc and loader
+    When I launch the process
+    Then the process should print the path to the loader
+
+  Scenario: Load libraries from the bundled libc's directory
+    Given the executable "/bin/sh -c 'grep -m 1 -o /.*libc.so.6 /proc/$$/maps'"
+    And the system's libc copied to a new directory and loader
+    When I launch the process
+    Then the process should print the path to the libc
+
+  Scenario: Keep argv[0] when running through a loader
+    Given the executable "/bin/sh -c 'echo $0'"
+    And the system's libc and loader
+    When I launch the process
+    Then the process should print the line "/bin/sh"
+
+  Scenario: Override argv[0] when running through a loader
+    Given the executable "/bin/sh -c 'echo $0'"
+    And the system's libc and loader
+    And argv[0] set to "pwn"
+    When I launch the process
+    Then the process should print the line "pwn"
+
+  Scenario: Choose the transport at runtime
+    Given the process "sh -c 'echo $$; exec cat'"
+    When I wrap the process in an AnyInteraction
+    Then the wrapped process should print its PID
+    When I send the line "hello" to the wrapped process
+    Then the wrapped process should print the line "hello"
@@ -0,0 +1,15 @@
+Feature: TCP interactions
+  In order to exploit network services and catch callbacks
+  As a hacker
+  I want to interact with TCP connections in both directions
+
+  Scenario: Catch a reverse connection
+    Given a TCP listener on "127.0.0.1:0"
+    When a client connects to the listener and sends "hello\n"
+    And I accept a connection
+    Then the accepted connection should receive the line "hello\n"
+
+  Scenario: Accept timeout
+    Given a TCP listener on "127.0.0.1:0"
+    When I accept a connection within 100 milliseconds
+    Then the accept should time out
@@ -0,0 +1,26 @@
+Feature: TLS interactions
+  In order to exploit services that are wrapped in TLS
+  As a hacker
+  I want to interact with TLS connections
+
+  Scenario: Trust a custom certificate authority
+    Given a TLS server for "localhost"
+    When I connect to the TLS server as "localhost", trusting its certificate
+    Then the TLS connection should receive the line "hello localhost"
+
+  Scenario: Reject an untrusted certificate
+    Given a TLS server for "localhost"
+    When I connect to the TLS server as "localhost"
+    Then the TLS connection should fail
+
+  Scenario: Skip certificate verification
+    Given a TLS server for "localhost"
+    When I connect to the TLS server as "challenge.example.com", without verification
+    Then the TLS connection should receive the line "hello challenge.example.com"
+
+  Scenario: Authenticate with a client certificate
+    Given a TLS server for "localhost" requiring a client certificate
+    When I connect to the TLS server as "localhost", trusting its certificate
+    Then the TLS connection should fail
+    When I connect to the TLS server as "localhost", trusting its certificate with a client certificate
+    Then the TLS connection should receive the line "hello localhost"
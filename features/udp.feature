@@ -0,0 +1,30 @@
+Feature: UDP interactions
+  In order to exploit UDP-only services
+  As a hacker
+  I want to interact with UDP sockets one datagram at a time
+
+  Background:
+    Given a UDP server that answers each datagram with the datagram and "!"
+
+  Scenario: Datagrams received together form one chunk
+    When I send "ping" over UDP
+    And I read a chunk over UDP
+    Then the data I received over UDP should equal "ping!"
+
+  Scenario: Receive single datagrams
+    When I send "ping" over UDP
+    And I receive a datagram
+    Then the data I received over UDP should equal "ping"
+    When I receive a datagram
+    Then the data I received over UDP should equal "!"
+
+  Scenario: Finish a partially read datagram
+    When I send "ping" over UDP
+    And I read 2 bytes over UDP
+    And I receive a datagram
+    Then the data I received over UDP should equal "ng"
+
+  Scenario: Skip empty datagrams
+    When I send an empty datagram over UDP
+    And I read a chunk over UDP
+    Then the data I received over UDP should equal "!"
@@ -0,0 +1,42 @@
+Feature: Unix domain socket and FIFO interactions
+  In order to exploit local daemons
+  As a hacker
+  I want to interact with Unix domain sockets and named pipes
+
+  Scenario: Connect to a socket on the filesystem
+    Given a Unix socket server at "server.sock" that greets with "hello"
+    When I connect to the Unix socket at "server.sock"
+    Then the Unix interaction should receive the line "hello"
+    And the Unix interaction's PID should be this process's
+
+  Scenario: Connect to an abstract socket
+    Given a Unix socket server at "@" that greets with "hello"
+    When I connect to the Unix socket at "@"
+    Then the Unix interaction should receive the line "hello"
+
+  Scenario: Listen on a socket
+    Given a Unix socket listener at "listener.sock"
+    When a client connects to "listener.sock" and sends "hello"
+    And I accept a Unix socket connection
+    Then the Unix interaction should receive the line "hello"
+
+  Scenario: Talk over a pair of FIFOs
+    Given the FIFOs "in" and "out"
+    When I open a FIFO interaction reading from "in" and writing to "out"
+    And the other side writes "hello" into "in"
+    Then the Unix interaction should receive the line "hello"
+    When I send the line "bye" through the FIFO interaction
+    Then the other side should read "bye" from "out"
+
+  Scenario: Wait for a writer before reading from a FIFO
+    Given the FIFOs "in" and "out"
+    When I open a FIFO interaction reading from "in" and writing to "out"
+    And the other side writes "hello" into "in" after 100 milliseconds
+    Then the Unix interaction should receive the line "hello"
+    And the FIFO interaction should reach the end of its input
+
+  Scenario: Wait for a reader before writing to a FIFO
+    Given the FIFOs "in" and "out"
+    When I open a FIFO interaction reading from "in" and writing to "out" before the other side opens it
+    And I send the line "bye" through the FIFO interaction
+    Then the other side should read "bye" from "out"
@@ -0,0 +1,186 @@
+#[cfg(all(feature = "ssh", feature = "tcp"))]
+use super::ssh::Forward;
+#[cfg(feature = "ssh")]
+use super::ssh::SSH;
+#[cfg(feature = "stdio")]
+use super::stdio::Stdio;
+#[cfg(feature = "tcp")]
+use super::tcp::Tcp;
+#[cfg(feature = "tls")]
+use super::tls::Tls;
+#[cfg(feature = "udp")]
+use super::udp::Udp;
+#[cfg(feature = "unix")]
+use super::unix::{Fifo, Unix};
+use super::{Interaction, PID, Timing, transcript::Replay};
+use std::{
+    error::Error,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/**
+Any of the built-in [interactions](Interaction), for when the transport is only chosen at runtime.
+```no_run
+use libspl::{Interaction, interact, interaction::AnyInteraction};
+
+# use std::error::Error;
+# #[tokio::main]
+# async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+let mut interaction: AnyInteraction = match std::env::args().nth(1).as_deref() {
+    Some("remote") => interact!(tcp, "challenge.example.com:1337").await?.into(),
+    _ => interact!(stdio, "./vuln").await?.into(),
+};
+interaction.send_line(b"hello").await?;
+# Ok(())
+# }
+```
+
+Each variant keeps the [`Timing`] of the interaction it holds.
+*/
+#[derive(Debug)]
+pub enum AnyInteraction {
+    #[cfg(feature = "ssh")]
+    Ssh(SSH),
+    /// A TCP connection through an SSH port forward.
+    #[cfg(all(feature = "ssh", feature = "tcp"))]
+    Forward(Forward),
+    #[cfg(feature = "stdio")]
+    Stdio(Stdio),
+    #[cfg(feature = "tcp")]
+    Tcp(Tcp),
+    /// Boxed, since TLS streams are much larger than the other interactions.
+    #[cfg(feature = "tls")]
+    Tls(Box<Tls>),
+    #[cfg(feature = "udp")]
+    Udp(Udp),
+    #[cfg(feature = "unix")]
+    Unix(Unix),
+    #[cfg(feature = "unix")]
+    Fifo(Fifo),
+    Replay(Replay),
+}
+
+/// Evaluates `$body` with `$inner` bound to the interaction held by any variant of `$value`.
+macro_rules! dispatch {
+    ($value: expr, $inner: ident => $body: expr) => {
+        match $value {
+            #[cfg(feature = "ssh")]
+            AnyInteraction::Ssh($inner) => $body,
+            #[cfg(all(feature = "ssh", feature = "tcp"))]
+            AnyInteraction::Forward($inner) => $body,
+            #[cfg(feature = "stdio")]
+            AnyInteraction::Stdio($inner) => $body,
+            #[cfg(feature = "tcp")]
+            AnyInteraction::Tcp($inner) => $body,
+            #[cfg(feature = "tls")]
+            AnyInteraction::Tls($inner) => $body,
+            #[cfg(feature = "udp")]
+            AnyInteraction::Udp($inner) => $body,
+            #[cfg(feature = "unix")]
+            AnyInteraction::Unix($inner) => $body,
+            #[cfg(feature = "unix")]
+            AnyInteraction::Fifo($inner) => $body,
+            AnyInteraction::Replay($inner) => $body,
+        }
+    };
+}
+
+macro_rules! from {
+    ($($(#[$attribute: meta])* $variant: ident($type: ty)),+ $(,)?) => {
+        $(
+            $(#[$attribute])*
+            impl From<$type> for AnyInteraction {
+                fn from(interaction: $type) -> Self {
+                    Self::$variant(interaction)
+                }
+            }
+        )+
+    };
+}
+from! {
+    #[cfg(feature = "ssh")]
+    Ssh(SSH),
+    #[cfg(all(feature = "ssh", feature = "tcp"))]
+    Forward(Forward),
+    #[cfg(feature = "stdio")]
+    Stdio(Stdio),
+    #[cfg(feature = "tcp")]
+    Tcp(Tcp),
+    #[cfg(feature = "tls")]
+    Tls(Box<Tls>),
+    #[cfg(feature = "udp")]
+    Udp(Udp),
+    #[cfg(feature = "unix")]
+    Unix(Unix),
+    #[cfg(feature = "unix")]
+    Fifo(Fifo),
+    Replay(Replay),
+}
+#[cfg(feature = "tls")]
+impl From<Tls> for AnyInteraction {
+    fn from(interaction: Tls) -> Self {
+        Self::Tls(Box::new(interaction))
+    }
+}
+
+impl AsyncRead for AnyInteraction {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        dispatch!(self.get_mut(), inner => Pin::new(inner).poll_read(cx, buf))
+    }
+}
+impl AsyncWrite for AnyInteraction {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        dispatch!(self.get_mut(), inner => Pin::new(inner).poll_write(cx, buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        dispatch!(self.get_mut(), inner => Pin::new(inner).poll_flush(cx))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        dispatch!(self.get_mut(), inner => Pin::new(inner).poll_shutdown(cx))
+    }
+}
+impl Interaction for AnyInteraction {
+    /// Unused, since every variant brings its own [`Timing`].
+    const TIMEOUT: Duration = Duration::ZERO;
+
+    async fn close(self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        dispatch!(self, inner => inner.close().await)
+    }
+
+    fn timing(&self) -> &Timing {
+        dispatch!(self, inner => inner.timing())
+    }
+
+    fn timing_mut(&mut self) -> &mut Timing {
+        dispatch!(self, inner => inner.timing_mut())
+    }
+}
+
+/// Fails with [`Unsupported`](io::ErrorKind::Unsupported) for variants that don't implement [`PID`].
+impl PID for AnyInteraction {
+    async fn get_pid(&self) -> Result<u32, Box<dyn Error + Send + Sync>> {
+        match self {
+            #[cfg(feature = "ssh")]
+            Self::Ssh(inner) => inner.get_pid().await,
+            #[cfg(feature = "stdio")]
+            Self::Stdio(inner) => inner.get_pid().await,
+            #[cfg(feature = "unix")]
+            Self::Unix(inner) => inner.get_pid().await,
+            _ => Err(Box::new(io::Error::from(io::ErrorKind::Unsupported))),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use super::{Interaction, PID, Timing};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+
+/// The number of bytes requested from the wrapped [`Interaction`] at once.
+pub(super) const CAPACITY: usize = 4096;
+
+/// Moves as much data from the front of `buffer` into `buf` as fits.
+pub(super) fn drain_into(buffer: &mut VecDeque<u8>, buf: &mut ReadBuf<'_>) {
+    let n = buf.remaining().min(buffer.len());
+    let (front, back) = buffer.as_slices();
+    let from_front = n.min(front.len());
+    buf.put_slice(&front[..from_front]);
+    buf.put_slice(&back[..n - from_front]);
+    buffer.drain(..n);
+}
+
+/**
+Hands out data from `buffer` to `buf`, first refilling `buffer` from `inner` (in a single read of up
+to [`CAPACITY`] bytes) if it is empty. `received` is called with any data read from `inner`.
+*/
+pub(super) fn poll_read_buffered<R: AsyncRead + Unpin>(
+    inner: &mut R,
+    buffer: &mut VecDeque<u8>,
+    cx: &mut Context<'_>,
+    buf: &mut ReadBuf<'_>,
+    received: impl FnOnce(&[u8]),
+) -> Poll<io::Result<()>> {
+    if buffer.is_empty() {
+        let mut fill = [0; CAPACITY];
+        let mut fill = ReadBuf::new(&mut fill);
+        ready!(Pin::new(inner).poll_read(cx, &mut fill))?;
+        received(fill.filled());
+        buffer.extend(fill.filled());
+    }
+    drain_into(buffer, buf);
+    Poll::Ready(Ok(()))
+}
+
+/**
+An [`Interaction`] that keeps an internal receive buffer between itself and the wrapped
+[`Interaction`].
+
+Data is pulled from the wrapped [`Interaction`] in large reads and handed out from the buffer, so
+content-based reads (e.g. [`read_until`](Interaction::read_until)) are cheap, and anything that was
+read too eagerly can be pushed back with [`unread`](Buffered::unread) without being lost.
+```
+use libspl::{interact, Interaction, interaction::Buffered};
+
+# use std::error::Error;
+# #[tokio::main]
+# async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+let mut interaction = Buffered::new(interact!(stdio, "echo", "hello world").await?);
+assert_eq!(interaction.peek().await?, b"hello world\n");
+let hello = interaction.read_until(b" ", None).await?;
+interaction.unread(&hello);
+assert_eq!(interaction.read_line(None).await?, b"hello world\n");
+# Ok(())
+# }
+```
+*/
+#[derive(Debug)]
+pub struct Buffered<I> {
+    inner: I,
+    buffer: VecDeque<u8>,
+}
+impl<I> Buffered<I> {
+    pub fn new(inner: I) -> Self {
+        Self {
+            inner,
+            buffer: VecDeque::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &I {
+        &self.inner
+    }
+
+    /**
+    Accesses the wrapped [`Interaction`] directly. Reading from it will bypass any data that is
+    still in the buffer.
+    */
+    pub fn get_mut(&mut self) -> &mut I {
+        &mut self.inner
+    }
+
+    /// Unwraps the [`Interaction`], returning it along with any data still in the buffer.
+    pub fn into_inner(self) -> (I, Vec<u8>) {
+        (self.inner, self.buffer.into())
+    }
+
+    /// The data that has been received but not yet read.
+    pub fn pending(&self) -> &VecDeque<u8> {
+        &self.buffer
+    }
+
+    /// Pushes `data` back onto the front of the buffer, so that it will be read again next.
+    pub fn unread(&mut self, data: &[u8]) {
+        for &byte in data.iter().rev() {
+            self.buffer.push_front(byte);
+        }
+    }
+}
+impl<I: Interaction> Buffered<I> {
+    /**
+    Returns the data that has been received but not yet read, without consuming it. If the buffer
+    is empty, waits until *some* data is received from the remote stream first. An empty slice
+    means the remote stream has ended.
+    */
+    pub async fn peek(&mut self) -> io::Result<&[u8]> {
+        if self.buffer.is_empty() {
+            let mut buf = vec![0; CAPACITY];
+            let n = self.inner.read(&mut buf).await?;
+            self.buffer.extend(&buf[..n]);
+        }
+        Ok(self.buffer.make_contiguous())
+    }
+
+    /**
+    Discards everything pending: the contents of the buffer, and anything else the remote stream
+    sends before it goes quiet (see [`read_last_chunk_bytes`](Interaction::read_last_chunk_bytes)).
+    Returns the discarded data.
+    */
+    pub async fn clean(&mut self) -> Vec<u8> {
+        let mut discarded: Vec<u8> = self.buffer.drain(..).collect();
+        discarded.append(&mut self.inner.read_last_chunk_bytes().await);
+        discarded
+    }
+}
+impl<I: AsyncRead + Unpin> AsyncRead for Buffered<I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        poll_read_buffered(&mut this.inner, &mut this.buffer, cx, buf, |_| {})
+    }
+}
+impl<I: AsyncWrite + Unpin> AsyncWrite for Buffered<I> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+impl<I: Interaction + Send> Interaction for Buffered<I> {
+    const TIMEOUT: Duration = I::TIMEOUT;
+    const REPEAT: usize = I::REPEAT;
+
+    async fn close(self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.inner.close().await
+    }
+
+    fn timing(&self) -> &Timing {
+        self.inner.timing()
+    }
+
+    fn timing_mut(&mut self) -> &mut Timing {
+        self.inner.timing_mut()
+    }
+}
+
+impl<I: PID> PID for Buffered<I> {
+    async fn get_pid(&self) -> Result<u32, Box<dyn Error + Send + Sync>> {
+        self.inner.get_pid().await
+    }
+}
@@ -0,0 +1,176 @@
+/*!
+Configurable echoing of the data exchanged by [`run`](super::Interaction::run) and friends.
+```no_run
+use libspl::{interact, Interaction, interaction::echo::{Echo, Format}};
+
+# use std::error::Error;
+# #[tokio::main]
+# async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+let mut interaction = interact!(tcp, "www.example.com:65535").await?;
+let (_, future) = interaction.run_with_byte_channel_and_echo(
+    [b"1".as_slice()],
+    Echo::writer(tokio::io::stderr()).format(Format::Hexdump),
+);
+future.await?;
+# Ok(())
+# }
+```
+*/
+
+use super::Direction;
+use std::{fmt::Write, io};
+use tokio::io::{AsyncWrite, AsyncWriteExt, stdout};
+
+/// How an [`Echo`] presents data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Data is written as-is. Sent data is followed by a newline if it doesn't already end in one.
+    #[default]
+    Raw,
+    /// Like [`Raw`](Format::Raw), but coloured by [`Direction`] using ANSI escape codes.
+    Colored,
+    /**
+    Each transmission is written on its own line, prefixed with `<< ` (received) or `>> ` (sent),
+    with non-printable bytes escaped.
+    */
+    Escaped,
+    /// Each transmission is written as a header line followed by a canonical hex dump.
+    Hexdump,
+}
+
+type Log = Box<dyn FnMut(Direction, &[u8]) + Send>;
+
+enum Sink {
+    Writer(Box<dyn AsyncWrite + Send + Unpin>),
+    Log(Log),
+    Silent,
+}
+
+/// Where (and how) data exchanged with an [`Interaction`](super::Interaction) is echoed.
+pub struct Echo {
+    sink: Sink,
+    format: Format,
+}
+impl Echo {
+    /// Echoes to [`stdout`]. This is the default.
+    pub fn stdout() -> Self {
+        Self::writer(stdout())
+    }
+
+    /// Echoes to `writer`.
+    pub fn writer(writer: impl AsyncWrite + Send + Unpin + 'static) -> Self {
+        Self {
+            sink: Sink::Writer(Box::new(writer)),
+            format: Format::default(),
+        }
+    }
+
+    /// Passes each transmission, already formatted, to `log`.
+    pub fn log(log: impl FnMut(Direction, &[u8]) + Send + 'static) -> Self {
+        Self {
+            sink: Sink::Log(Box::new(log)),
+            format: Format::default(),
+        }
+    }
+
+    /// Discards everything.
+    pub fn silent() -> Self {
+        Self {
+            sink: Sink::Silent,
+            format: Format::default(),
+        }
+    }
+
+    /// Sets the [`Format`] used to present data.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Echoes `data`, which was transmitted in `direction`.
+    pub async fn echo(&mut self, direction: Direction, data: &[u8]) -> io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        match &mut self.sink {
+            Sink::Writer(writer) => {
+                writer
+                    .write_all(&self.format.apply(direction, data))
+                    .await?;
+                writer.flush().await
+            }
+            Sink::Log(log) => {
+                log(direction, &self.format.apply(direction, data));
+                Ok(())
+            }
+            Sink::Silent => Ok(()),
+        }
+    }
+}
+impl Default for Echo {
+    fn default() -> Self {
+        Self::stdout()
+    }
+}
+
+impl Format {
+    fn apply(self, direction: Direction, data: &[u8]) -> Vec<u8> {
+        let newline: &[u8] = if direction == Direction::Sent && !data.ends_with(b"\n") {
+            b"\n"
+        } else {
+            b""
+        };
+        match self {
+            Format::Raw => [data, newline].concat(),
+            Format::Colored => [
+                match direction {
+                    Direction::Received => b"\x1b[32m",
+                    Direction::Sent => b"\x1b[31m",
+                },
+                data,
+                b"\x1b[0m",
+                newline,
+            ]
+            .concat(),
+            Format::Escaped => {
+                format!("{} {}\n", direction.arrow(), data.escape_ascii()).into_bytes()
+            }
+            Format::Hexdump => {
+                let mut r = format!("{} {} bytes\n", direction.arrow(), data.len());
+                for (index, line) in data.chunks(16).enumerate() {
+                    let _ = write!(r, "{:08x} ", index * 16);
+                    for column in 0..16 {
+                        if column == 8 {
+                            r.push(' ');
+                        }
+                        match line.get(column) {
+                            Some(byte) => {
+                                let _ = write!(r, " {byte:02x}");
+                            }
+                            None => r.push_str("   "),
+                        }
+                    }
+                    r.push_str("  |");
+                    r.extend(line.iter().map(|&byte| {
+                        if byte.is_ascii_graphic() || byte == b' ' {
+                            byte as char
+                        } else {
+                            '.'
+                        }
+                    }));
+                    r.push_str("|\n");
+                }
+                r.into_bytes()
+            }
+        }
+    }
+}
+
+impl Direction {
+    fn arrow(self) -> &'static str {
+        match self {
+            Direction::Received => "<<",
+            Direction::Sent => ">>",
+        }
+    }
+}
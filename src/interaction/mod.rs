@@ -1,21 +1,56 @@
 #![cfg(feature = "interaction")]
 
-use std::{error::Error, future::Future, time::Duration};
+pub use regex::bytes::RegexBuilder;
+use regex_automata::{
+    hybrid::{
+        LazyStateID,
+        dfa::{Cache, DFA},
+    },
+    util::start,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    future::Future,
+    io, mem,
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
 use tokio::{
     io::{
-        AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
-        Error as IOError, copy, split, stdin, stdout,
+        AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, copy,
+        split, stdin, stdout,
     },
     join,
     sync::mpsc::{UnboundedReceiver, unbounded_channel},
-    time::timeout,
+    time::{Instant, timeout, timeout_at},
 };
 
+mod any;
+mod buffered;
+pub mod echo;
+pub mod pty;
+pub mod script;
 pub mod ssh;
 pub mod stdio;
 pub mod tcp;
+pub mod tls;
+pub mod transcript;
+pub mod udp;
+pub mod unix;
+
+pub use any::AnyInteraction;
+pub use buffered::Buffered;
+use echo::Echo;
+
+/**
+A read-write stream that reacts to input.
 
-/// A read-write stream that reacts to input.
+[`TIMEOUT`](Interaction::TIMEOUT) and [`REPEAT`](Interaction::REPEAT) are the defaults for each
+new instance's [`Timing`], which can be adjusted at runtime (see
+[`set_timeout`](Interaction::set_timeout) and [`with_timeout`](Interaction::with_timeout)).
+*/
 #[trait_variant::make(Send)]
 pub trait Interaction: AsyncRead + AsyncWrite + Unpin + Sized {
     const TIMEOUT: Duration;
@@ -24,25 +59,70 @@ pub trait Interaction: AsyncRead + AsyncWrite + Unpin + Sized {
     /// Ends and cleans up the underlying tissue of the [Interaction].
     async fn close(self) -> Result<(), Box<dyn Error + Send + Sync>>;
 
-    /// Reads the last chunk. See [`read_chunk`](Interaction::read_chunk)
-    async fn read_last_chunk(&mut self) -> String {
+    /// The [`Timing`] currently used to detect the end of a chunk.
+    fn timing(&self) -> &Timing;
+
+    /// See [`timing`](Interaction::timing).
+    fn timing_mut(&mut self) -> &mut Timing;
+
+    /// Sets the amount of time without new data after which a chunk is considered to have ended.
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.timing_mut().timeout = timeout;
+    }
+
+    /// Sets the number of consecutive timeouts required for a chunk to be considered ended.
+    fn set_repeat(&mut self, repeat: usize) {
+        self.timing_mut().repeat = repeat;
+    }
+
+    /**
+    Overrides the timeout until the returned guard is dropped, then restores the previous
+    [`Timing`]. The guard dereferences to `self`.
+    ```no_run
+    use libspl::{interact, Interaction};
+    use std::time::Duration;
+
+    # use std::error::Error;
+    # #[tokio::main]
+    # async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut interaction = interact!(tcp, "www.example.com:65535").await?;
+    let banner = interaction.with_timeout(Duration::from_secs(1)).read_chunk().await?;
+    # Ok(())
+    # }
+    ```
+    */
+    fn with_timeout(&mut self, timeout: Duration) -> Scoped<'_, Self> {
+        let timing = *self.timing();
+        self.set_timeout(timeout);
+        Scoped {
+            interaction: self,
+            timing,
+        }
+    }
+
+    /// Reads the last chunk. See [`read_chunk_bytes`](Interaction::read_chunk_bytes)
+    async fn read_last_chunk_bytes(&mut self) -> Vec<u8> {
         async {
             let mut buf = Vec::new();
-            let mut dropped = vec![false; Self::REPEAT];
+            let Timing {
+                timeout: duration,
+                repeat,
+            } = *self.timing();
+            let mut dropped = vec![false; repeat];
             'a: loop {
-                match timeout(Self::TIMEOUT, self.read_u8()).await {
+                match timeout(duration, self.read_u8()).await {
                     Ok(Ok(b)) => {
-                        dropped = vec![false; Self::REPEAT];
+                        dropped = vec![false; repeat];
                         buf.push(b);
                     }
                     _ => {
-                        for i in 0..Self::REPEAT {
-                            if !dropped[i] {
-                                dropped[i] = true;
+                        for dropped in dropped.iter_mut() {
+                            if !*dropped {
+                                *dropped = true;
                                 continue 'a;
                             }
                         }
-                        return String::from_utf8_lossy(&buf).into_owned();
+                        return buf;
                     }
                 }
             }
@@ -50,28 +130,232 @@ pub trait Interaction: AsyncRead + AsyncWrite + Unpin + Sized {
     }
 
     /**
-    Reads one "chunk" of remote input. A chunk "ends" when no new data is received for
-    [`TIMEOUT`](Interaction::TIMEOUT) amount of time. This does not apply to the first byte read --
-    the function will wait indefinitely until it receives *some* data from the remote stream.
+    Like [`read_last_chunk_bytes`](Interaction::read_last_chunk_bytes), but lossily converts the
+    chunk to a [`String`].
     */
-    async fn read_chunk(&mut self) -> Result<String, Box<dyn Error + Send + Sync>> {
+    async fn read_last_chunk(&mut self) -> String {
+        async { String::from_utf8_lossy(&self.read_last_chunk_bytes().await).into_owned() }
+    }
+
+    /**
+    Reads one "chunk" of remote input. A chunk "ends" when no new data is received for the
+    [`Timing`]'s timeout amount of time. This does not apply to the first byte read -- the function
+    will wait indefinitely until it receives *some* data from the remote stream.
+    */
+    async fn read_chunk_bytes(&mut self) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
         async {
-            Ok(
-                String::from_utf8_lossy(&[self.read_u8().await?]).into_owned()
-                    + &self.read_last_chunk().await,
-            )
+            let mut buf = vec![self.read_u8().await?];
+            buf.append(&mut self.read_last_chunk_bytes().await);
+            Ok(buf)
         }
     }
 
     /**
-    Like [`run`](Interaction::run), but forwards input received from the remote process over an
-    [unbounded channel](tokio::sync::mpsc::unbounded_channel).
+    Like [`read_chunk_bytes`](Interaction::read_chunk_bytes), but lossily converts the chunk to a
+    [`String`].
     */
-    fn run_with_channel<'a, I>(
+    async fn read_chunk(&mut self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        async { Ok(String::from_utf8_lossy(&self.read_chunk_bytes().await?).into_owned()) }
+    }
+
+    /**
+    Reads from the remote stream until `delimiter` is encountered, and returns everything that was
+    read (including `delimiter`). Data is read one byte at a time, so nothing past the end of
+    `delimiter` is consumed from the stream.
+
+    If a `deadline` is provided and it elapses before `delimiter` is found, an [`Incomplete`] error
+    of kind [`TimedOut`](io::ErrorKind::TimedOut) is returned.
+    */
+    async fn read_until(
+        &mut self,
+        delimiter: &[u8],
+        deadline: Option<Duration>,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        async move {
+            let deadline = deadline.map(|deadline| Instant::now() + deadline);
+            let mut buf = Vec::new();
+            while !buf.ends_with(delimiter) {
+                read_byte(self, &mut buf, deadline).await?;
+            }
+            Ok(buf)
+        }
+    }
+
+    /// Shorthand to call [`read_until`](Interaction::read_until) with `delimiter = b"\n"`.
+    async fn read_line(
+        &mut self,
+        deadline: Option<Duration>,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        self.read_until(b"\n", deadline)
+    }
+
+    /**
+    Reads exactly `n` bytes from the remote stream.
+
+    If a `deadline` is provided and it elapses before `n` bytes are received, an [`Incomplete`]
+    error of kind [`TimedOut`](io::ErrorKind::TimedOut) is returned.
+    */
+    async fn read_exact_n(
+        &mut self,
+        n: usize,
+        deadline: Option<Duration>,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        async move {
+            let deadline = deadline.map(|deadline| Instant::now() + deadline);
+            let mut buf = Vec::with_capacity(n);
+            while buf.len() < n {
+                read_byte(self, &mut buf, deadline).await?;
+            }
+            Ok(buf)
+        }
+    }
+
+    /// Writes `data` followed by a newline to the remote stream.
+    async fn send_line(&mut self, data: &[u8]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        async move {
+            self.write_all(&[data, b"\n"].concat()).await?;
+            self.flush().await?;
+            Ok(())
+        }
+    }
+
+    /**
+    Waits for `prompt` (see [`read_until`](Interaction::read_until)), then writes `data` to the
+    remote stream. Returns everything that was received before `prompt`.
+    */
+    async fn send_after(
+        &mut self,
+        prompt: &[u8],
+        data: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        async move {
+            let mut before = self.read_until(prompt, None).await?;
+            before.truncate(before.len() - prompt.len());
+            self.write_all(data).await?;
+            self.flush().await?;
+            Ok(before)
+        }
+    }
+
+    /**
+    Like [`send_after`](Interaction::send_after), but uses [`send_line`](Interaction::send_line).
+    */
+    async fn send_line_after(
+        &mut self,
+        prompt: &[u8],
+        data: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        async move {
+            let mut before = self.read_until(prompt, None).await?;
+            before.truncate(before.len() - prompt.len());
+            self.send_line(data).await?;
+            Ok(before)
+        }
+    }
+
+    /**
+    Reads from the remote stream until one of `patterns` matches the data received so far, and
+    returns which pattern matched along with its capture groups and the data that preceded the
+    match. If more than one pattern matches at once, the first one in `patterns` wins.
+
+    Patterns are tested after every byte received, so a trailing greedy pattern (e.g. `Error: (.*)`)
+    will match as soon as possible. Terminate such patterns explicitly (e.g. `Error: (.*)\n`).
+
+    Patterns are matched incrementally, so the cost of each byte doesn't grow with the amount of
+    data received before it. Patterns built with a [`RegexBuilder`] can't be matched incrementally,
+    so everything received is searched again after each byte instead. To avoid that, give their
+    flags inline (e.g. `(?i)`) and use [`Regex::new`].
+
+    If the remote stream ends, or a `deadline` is provided and it elapses before any pattern
+    matches, an [`Incomplete`] error holding everything read so far is returned.
+    ```no_run
+    use libspl::{interact, Interaction, interaction::Regex};
+
+    # use std::error::Error;
+    # #[tokio::main]
+    # async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut interaction = interact!(tcp, "www.example.com:65535").await?;
+    let expected = interaction
+        .expect(&[Regex::new(r"Your choice \(1-4\):")?, Regex::new(r"Error: (.*)\n")?], None)
+        .await?;
+    if expected.index == 1 {
+        println!("{}", String::from_utf8_lossy(expected.group(1).unwrap()));
+    }
+    # Ok(())
+    # }
+    ```
+    */
+    async fn expect(
+        &mut self,
+        patterns: &[Regex],
+        deadline: Option<Duration>,
+    ) -> Result<Expected, Box<dyn Error + Send + Sync>> {
+        async move {
+            let deadline = deadline.map(|deadline| Instant::now() + deadline);
+            let mut matchers = patterns.iter().map(Matcher::new).collect::<Vec<_>>();
+            let mut buf = Vec::new();
+            loop {
+                read_byte(self, &mut buf, deadline).await?;
+                for (index, (pattern, matcher)) in patterns.iter().zip(&mut matchers).enumerate() {
+                    if !matcher.matches(pattern, &buf) {
+                        continue;
+                    }
+                    if let Some(captures) = pattern.captures(&buf) {
+                        let whole = captures.get(0).unwrap();
+                        return Ok(Expected {
+                            index,
+                            before: buf[..whole.start()].to_vec(),
+                            groups: captures
+                                .iter()
+                                .map(|group| group.map(|group| group.as_bytes().to_vec()))
+                                .collect(),
+                            named: pattern
+                                .capture_names()
+                                .flatten()
+                                .filter_map(|name| {
+                                    Some((
+                                        name.to_owned(),
+                                        captures.name(name)?.as_bytes().to_vec(),
+                                    ))
+                                })
+                                .collect(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+    Like [`run`](Interaction::run), but forwards each chunk of input received from the remote
+    process, unmodified, over an [unbounded channel](tokio::sync::mpsc::unbounded_channel).
+    */
+    #[allow(clippy::type_complexity)]
+    fn run_with_byte_channel<'a, I>(
         &mut self,
         input: I,
     ) -> (
-        UnboundedReceiver<String>,
+        UnboundedReceiver<Vec<u8>>,
+        impl Future<Output = Result<(), Box<dyn Error + Send + Sync>>> + Send,
+    )
+    where
+        I: IntoIterator<Item = &'a [u8]> + Send,
+        <I as IntoIterator>::IntoIter: Send,
+    {
+        self.run_with_byte_channel_and_echo(input, Echo::default())
+    }
+
+    /**
+    Like [`run_with_byte_channel`](Interaction::run_with_byte_channel), but echoes the data that is
+    exchanged using `echo` instead of writing it to [`stdout`].
+    */
+    #[allow(clippy::type_complexity)]
+    fn run_with_byte_channel_and_echo<'a, I>(
+        &mut self,
+        input: I,
+        mut echo: Echo,
+    ) -> (
+        UnboundedReceiver<Vec<u8>>,
         impl Future<Output = Result<(), Box<dyn Error + Send + Sync>>> + Send,
     )
     where
@@ -80,34 +364,81 @@ pub trait Interaction: AsyncRead + AsyncWrite + Unpin + Sized {
     {
         let (sender, receiver) = unbounded_channel();
         let future = async move {
-            let mut stdout = stdout();
             for i in input {
-                let chunk = self.read_chunk().await?;
+                let chunk = self.read_chunk_bytes().await?;
+                echo.echo(Direction::Received, &chunk).await?;
                 if !sender.is_closed() {
-                    for part in chunk.split("\n") {
-                        sender.send(part.to_owned())?;
-                    }
+                    sender.send(chunk)?;
                 }
-                stdout.write_all(chunk.as_bytes()).await?;
-                let (r1, r2) = join!(self.write_all(i), async {
-                    stdout.write_all(i).await?;
-                    stdout.write_u8(b'\n').await?;
-                    Ok::<(), IOError>(())
-                });
+                let (r1, r2) = join!(self.write_all(i), echo.echo(Direction::Sent, i));
                 r1?;
                 r2?;
             }
 
-            let chunk: String = self.read_last_chunk().await;
-            for string in chunk.split("\n") {
-                if !sender.is_closed() {
-                    sender.send(string.to_owned()).unwrap();
+            let chunk = self.read_last_chunk_bytes().await;
+            echo.echo(Direction::Received, &chunk).await?;
+            if !sender.is_closed() {
+                sender.send(chunk)?;
+            }
+
+            let mut buf = vec![0; 4096];
+            loop {
+                match self.read(&mut buf).await? {
+                    0 => return Ok(()),
+                    n => echo.echo(Direction::Received, &buf[..n]).await?,
                 }
             }
-            stdout.write_all(chunk.as_bytes()).await?;
+        };
+        (receiver, future)
+    }
 
-            copy(self, &mut stdout).await?;
-            Ok(())
+    /**
+    Like [`run_with_byte_channel`](Interaction::run_with_byte_channel), but lossily converts each
+    chunk to a [`String`] and forwards it one line at a time.
+    */
+    fn run_with_channel<'a, I>(
+        &mut self,
+        input: I,
+    ) -> (
+        UnboundedReceiver<String>,
+        impl Future<Output = Result<(), Box<dyn Error + Send + Sync>>> + Send,
+    )
+    where
+        I: IntoIterator<Item = &'a [u8]> + Send,
+        <I as IntoIterator>::IntoIter: Send,
+    {
+        self.run_with_channel_and_echo(input, Echo::default())
+    }
+
+    /**
+    Like [`run_with_channel`](Interaction::run_with_channel), but echoes the data that is exchanged
+    using `echo` instead of writing it to [`stdout`].
+    */
+    fn run_with_channel_and_echo<'a, I>(
+        &mut self,
+        input: I,
+        echo: Echo,
+    ) -> (
+        UnboundedReceiver<String>,
+        impl Future<Output = Result<(), Box<dyn Error + Send + Sync>>> + Send,
+    )
+    where
+        I: IntoIterator<Item = &'a [u8]> + Send,
+        <I as IntoIterator>::IntoIter: Send,
+    {
+        let (sender, receiver) = unbounded_channel();
+        let (mut bytes, future) = self.run_with_byte_channel_and_echo(input, echo);
+        let future = async move {
+            join!(future, async move {
+                while let Some(chunk) = bytes.recv().await {
+                    for part in String::from_utf8_lossy(&chunk).split('\n') {
+                        if sender.send(part.to_owned()).is_err() {
+                            return;
+                        }
+                    }
+                }
+            })
+            .0
         };
         (receiver, future)
     }
@@ -123,7 +454,86 @@ pub trait Interaction: AsyncRead + AsyncWrite + Unpin + Sized {
         I: IntoIterator<Item = &'a [u8]> + Send,
         <I as IntoIterator>::IntoIter: Send,
     {
-        self.run_with_channel(input).1
+        self.run_with_byte_channel(input).1
+    }
+
+    /**
+    Repeatedly reads a chunk (see [`read_chunk_bytes`](Interaction::read_chunk_bytes)) and passes it
+    to `callback`, which decides what to do next by returning an [`Action`]. Chunks and input are
+    echoed to [`stdout`], as in [`run`](Interaction::run) (see
+    [`run_with_and_echo`](Interaction::run_with_and_echo) to change this).
+    ```no_run
+    use libspl::{interact, Interaction, interaction::Action};
+
+    # use std::error::Error;
+    # #[tokio::main]
+    # async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut stage = 0;
+    interact!(stdio, "./vuln")
+        .await?
+        .run_with(|chunk| {
+            stage += 1;
+            match stage {
+                1 => Action::SendLine(b"%3$p".to_vec()),
+                2 => {
+                    let leak = String::from_utf8_lossy(chunk);
+                    let base = u64::from_str_radix(leak.trim().trim_start_matches("0x"), 16)
+                        .unwrap()
+                        - 0x29d90;
+                    Action::Send([&[b'A'; 40][..], &(base + 0x50d70).to_le_bytes()].concat())
+                }
+                _ => Action::Interactive,
+            }
+        })
+        .await?;
+    # Ok(())
+    # }
+    ```
+    */
+    async fn run_with<F>(&mut self, callback: F) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        F: FnMut(&[u8]) -> Action + Send,
+    {
+        self.run_with_and_echo(callback, Echo::default())
+    }
+
+    /**
+    Like [`run_with`](Interaction::run_with), but echoes the data that is exchanged using `echo`
+    instead of writing it to [`stdout`].
+    */
+    async fn run_with_and_echo<F>(
+        &mut self,
+        mut callback: F,
+        mut echo: Echo,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        F: FnMut(&[u8]) -> Action + Send,
+    {
+        async move {
+            loop {
+                let chunk = self.read_chunk_bytes().await?;
+                echo.echo(Direction::Received, &chunk).await?;
+                let input = match callback(&chunk) {
+                    Action::Send(input) => input,
+                    Action::SendLine(mut input) => {
+                        input.push(b'\n');
+                        input
+                    }
+                    Action::Continue => continue,
+                    Action::Stop => return Ok(()),
+                    Action::Interactive => return Ok(inherit(&mut *self).await?),
+                };
+                let (r1, r2) = join!(
+                    async {
+                        self.write_all(&input).await?;
+                        self.flush().await
+                    },
+                    echo.echo(Direction::Sent, &input)
+                );
+                r1?;
+                r2?;
+            }
+        }
     }
 
     /**
@@ -134,13 +544,264 @@ pub trait Interaction: AsyncRead + AsyncWrite + Unpin + Sized {
     [`stdout`]: tokio::io::stdout
     */
     async fn inherit(self) -> Result<(), Box<dyn Error + Send + Sync>> {
-        async {
-            let ((mut read, mut write), mut stdin, mut stdout) = (split(self), stdin(), stdout());
-            let (a, b) = join!(copy(&mut read, &mut stdout), copy(&mut stdin, &mut write));
-            a?;
-            b?;
+        async { Ok(inherit(self).await?) }
+    }
+}
+
+/// Connects `stream` to [`stdin`]/[`stdout`] until both sides are finished.
+async fn inherit<S: AsyncRead + AsyncWrite>(stream: S) -> io::Result<()> {
+    let ((mut read, mut write), mut stdin, mut stdout) = (split(stream), stdin(), stdout());
+    let (a, b) = join!(copy(&mut read, &mut stdout), copy(&mut stdin, &mut write));
+    a?;
+    b?;
+    Ok(())
+}
+
+/// Which way data was travelling through an [`Interaction`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// From the remote stream.
+    Received,
+    /// To the remote stream.
+    Sent,
+}
+
+/// What [`run_with`](Interaction::run_with) should do after receiving a chunk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Write the bytes to the remote stream, then read the next chunk.
+    Send(Vec<u8>),
+    /// Write the bytes and a newline to the remote stream, then read the next chunk.
+    SendLine(Vec<u8>),
+    /// Read the next chunk without writing anything.
+    Continue,
+    /// Return.
+    Stop,
+    /// Connect the [`Interaction`] to stdin/stdout (see [`inherit`](Interaction::inherit)).
+    Interactive,
+}
+
+/**
+Controls how an [`Interaction`] detects the end of a chunk: a chunk ends once no new data has been
+received for `timeout`, `repeat` times in a row.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timing {
+    pub timeout: Duration,
+    pub repeat: usize,
+}
+impl Timing {
+    /// The default [`Timing`] for `I`, as described by its associated constants.
+    pub const fn of<I: Interaction>() -> Self {
+        Self {
+            timeout: I::TIMEOUT,
+            repeat: I::REPEAT,
+        }
+    }
+}
+
+/// Returned by [`with_timeout`](Interaction::with_timeout).
+pub struct Scoped<'a, I: Interaction> {
+    interaction: &'a mut I,
+    timing: Timing,
+}
+impl<I: Interaction> Deref for Scoped<'_, I> {
+    type Target = I;
+
+    fn deref(&self) -> &Self::Target {
+        self.interaction
+    }
+}
+impl<I: Interaction> DerefMut for Scoped<'_, I> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.interaction
+    }
+}
+impl<I: Interaction> Drop for Scoped<'_, I> {
+    fn drop(&mut self) {
+        *self.interaction.timing_mut() = self.timing;
+    }
+}
+
+/// The result of a successful call to [`expect`](Interaction::expect).
+#[derive(Clone, Debug)]
+pub struct Expected {
+    /// The position of the pattern that matched.
+    pub index: usize,
+    /// Everything that was received before the match.
+    pub before: Vec<u8>,
+    /// The capture groups of the match. The first group is always the match as a whole.
+    pub groups: Vec<Option<Vec<u8>>>,
+    /// The named capture groups of the match that participated in it.
+    pub named: HashMap<String, Vec<u8>>,
+}
+impl Expected {
+    /// The whole match.
+    pub fn matched(&self) -> &[u8] {
+        self.groups[0].as_deref().unwrap()
+    }
+
+    /// The capture group at `index`, if it participated in the match.
+    pub fn group(&self, index: usize) -> Option<&[u8]> {
+        self.groups.get(index)?.as_deref()
+    }
+
+    /// The capture group called `name`, if it participated in the match.
+    pub fn name(&self, name: &str) -> Option<&[u8]> {
+        self.named.get(name).map(Vec::as_slice)
+    }
+}
+
+/**
+A byte [regex](regex::bytes::Regex) for [`expect`](Interaction::expect), which remembers whether it
+was compiled from its [source](regex::bytes::Regex::as_str) alone.
+```
+use libspl::interaction::{Regex, RegexBuilder};
+
+# use std::error::Error;
+# fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+let prompt = Regex::new(r"(?i)your choice:")?;
+let same = Regex::from(RegexBuilder::new(r"your choice:").case_insensitive(true).build()?);
+assert!(prompt.is_match(b"YOUR CHOICE:") && same.is_match(b"YOUR CHOICE:"));
+# Ok(())
+# }
+```
+*/
+#[derive(Clone, Debug)]
+pub struct Regex {
+    regex: regex::bytes::Regex,
+    /// Whether `regex` has no flags other than those in its source (i.e. not from a builder).
+    plain: bool,
+}
+impl Regex {
+    /// Compiles `pattern` (see [`regex::bytes::Regex::new`]).
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            regex: regex::bytes::Regex::new(pattern)?,
+            plain: true,
+        })
+    }
+}
+impl Deref for Regex {
+    type Target = regex::bytes::Regex;
+
+    fn deref(&self) -> &Self::Target {
+        &self.regex
+    }
+}
+impl From<regex::bytes::Regex> for Regex {
+    /// Any flags `regex` was built with can't be recovered, so it is never matched incrementally.
+    fn from(regex: regex::bytes::Regex) -> Self {
+        Self {
+            regex,
+            plain: false,
+        }
+    }
+}
+impl Display for Regex {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.regex, f)
+    }
+}
+
+/**
+Tracks whether a pattern matches the data received so far as it arrives one byte at a time, so
+that [`expect`](Interaction::expect) doesn't have to search all of it again after every byte.
+*/
+struct Matcher {
+    /**
+    A lazy DFA for the pattern, and the state it reached after the data received so far. `None` if
+    the pattern can't be matched incrementally, in which case everything received is searched every
+    time.
+    */
+    dfa: Option<(DFA, Cache, LazyStateID)>,
+}
+impl Matcher {
+    fn new(pattern: &Regex) -> Self {
+        // The DFA is compiled from the source, so it would miss any flags the pattern was built with.
+        if !pattern.plain {
+            return Self { dfa: None };
+        }
+        let dfa = DFA::new(pattern.as_str()).ok().and_then(|dfa| {
+            let mut cache = dfa.create_cache();
+            let state = dfa.start_state(&mut cache, &start::Config::new()).ok()?;
+            Some((dfa, cache, state))
+        });
+        Self { dfa }
+    }
+
+    /// Whether `pattern` matches `buf`, the last byte of which has just been received.
+    fn matches(&mut self, pattern: &Regex, buf: &[u8]) -> bool {
+        if let Some((dfa, cache, state)) = &mut self.dfa {
+            let byte = *buf.last().unwrap();
+            /*
+            Matches are delayed by one byte: `next` reports matches that end before `byte`, and the
+            end of the data so far has to be fed as well for those that end after it.
+            */
+            match dfa.next_state(cache, *state, byte).and_then(|next| {
+                *state = next;
+                Ok((next, dfa.next_eoi_state(cache, next)?))
+            }) {
+                Ok((next, end)) if !next.is_quit() && !end.is_quit() => {
+                    return next.is_match() || end.is_match();
+                }
+                // e.g. a Unicode word boundary next to a non-ASCII byte.
+                _ => self.dfa = None,
+            }
+        }
+        pattern.is_match(buf)
+    }
+}
+
+/**
+Returned when the remote stream ends or a deadline passes before a read can complete. Holds
+everything that was read up to that point.
+*/
+#[derive(Debug)]
+pub struct Incomplete {
+    pub error: io::Error,
+    pub buffer: Vec<u8>,
+}
+impl Incomplete {
+    pub fn kind(&self) -> io::ErrorKind {
+        self.error.kind()
+    }
+}
+impl Display for Incomplete {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({} bytes received)", self.error, self.buffer.len())
+    }
+}
+impl Error for Incomplete {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/**
+Reads a single byte from `reader` onto the end of `buf`. Fails with
+[`TimedOut`](io::ErrorKind::TimedOut) if `deadline` passes first, in which case the contents of
+`buf` are moved into the [`Incomplete`] error.
+*/
+async fn read_byte<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    deadline: Option<Instant>,
+) -> Result<(), Incomplete> {
+    match match deadline {
+        Some(deadline) => timeout_at(deadline, reader.read_u8())
+            .await
+            .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into())),
+        None => reader.read_u8().await,
+    } {
+        Ok(byte) => {
+            buf.push(byte);
             Ok(())
         }
+        Err(error) => Err(Incomplete {
+            error,
+            buffer: mem::take(buf),
+        }),
     }
 }
 
@@ -169,45 +830,71 @@ pub trait PID: Interaction + Send + Sync {
     */
     async fn leak_pid(&self) -> &Self {
         async move {
-            match self.get_pid().await {
-                Ok(pid) => {
-                    cfg_if::cfg_if! {
-                        if #[cfg(feature = "clipboard")] {
-                            use clipboard_rs::{Clipboard, ClipboardContext};
-
-                            if let Ok(context) = ClipboardContext::new()
-                            {
-                                let _ = context.set_text(pid.to_string());
-                            }
-                        }
+            announce_pid(&self.get_pid().await);
+            wait_for_enter().await;
+            self
+        }
+    }
+}
+
+/// Writes `pid` to the console (and the clipboard, if enabled). See [`leak_pid`](PID::leak_pid).
+fn announce_pid(pid: &Result<u32, Box<dyn Error + Send + Sync>>) {
+    match pid {
+        Ok(pid) => {
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "clipboard")] {
+                    use clipboard_rs::{Clipboard, ClipboardContext};
+
+                    if let Ok(context) = ClipboardContext::new()
+                    {
+                        let _ = context.set_text(pid.to_string());
                     }
-                    println!("PID is {}", pid);
                 }
-                Err(error) => println!("Failed to retrieve PID with error: {}", error),
             }
-
-            /*
-            Using `print` here causes the line not to be shown until after ENTER is pressed, even if
-            `stdout.flush` is called afterwards.
-            */
-            let mut stdout = stdout();
-            stdout.write(b"[Press ENTER to continue]").await.unwrap();
-            stdout.flush().await.unwrap();
-
-            BufReader::new(stdin())
-                .read_line(&mut String::new())
-                .await
-                .unwrap();
-            self
+            println!("PID is {}", pid);
         }
+        Err(error) => println!("Failed to retrieve PID with error: {}", error),
     }
 }
 
+/// Prompts the user to press ENTER, and waits until they do.
+async fn wait_for_enter() {
+    /*
+    Using `print` here causes the line not to be shown until after ENTER is pressed, even if
+    `stdout.flush` is called afterwards.
+    */
+    let mut stdout = stdout();
+    stdout
+        .write_all(b"[Press ENTER to continue]")
+        .await
+        .unwrap();
+    stdout.flush().await.unwrap();
+
+    BufReader::new(stdin())
+        .read_line(&mut String::new())
+        .await
+        .unwrap();
+}
+
 /**
 Shorthand for creating new [Interaction]s.
 
+Any recipe can be followed by `;` and a list of [`Timing`] settings, which are applied to the new
+[Interaction] before it is returned:
+```no_run
+use libspl::interact;
+use std::time::Duration;
+
+# #[tokio::main]
+# async fn main() {
+let _ = interact!(tcp, "www.example.com:65535"; timeout = Duration::from_millis(500), repeat = 2)
+    .await
+    .unwrap();
+# }
+```
+
 Supported recipes:
-- [`ssh`]
+- [`ssh`] (see [`ssh::Builder`] for more options)
   ```no_run
   use libspl::interact;
 
@@ -216,7 +903,7 @@ Supported recipes:
   let _ = interact!(ssh, "www.example.com", "/path/to/executable").await.unwrap();
   # }
   ```
-- [`stdio`]
+- [`stdio`] (see [`stdio::Builder`] for more options)
   ```no_run
   use libspl::interact;
 
@@ -242,15 +929,116 @@ Supported recipes:
   let _ = interact!(tcp, "www.example.com:65535").await.unwrap();
   # }
   ```
+- [`listen`](tcp::listen)
+  ```no_run
+  use libspl::interact;
+
+  # #[tokio::main]
+  # async fn main() {
+  let _ = interact!(listen, "0.0.0.0:4444").await.unwrap();
+  # }
+  ```
+  ```no_run
+  use libspl::interact;
+  use std::time::Duration;
+
+  # #[tokio::main]
+  # async fn main() {
+  let _ = interact!(listen, "0.0.0.0:4444", Duration::from_secs(30)).await.unwrap();
+  # }
+  ```
+- [`bind`](tcp::Listener::bind), to find out which port was chosen before accepting (this returns a
+  [`Listener`](tcp::Listener) rather than an [Interaction], so it takes no settings)
+  ```no_run
+  use libspl::interact;
+  use std::time::Duration;
+
+  # #[tokio::main]
+  # async fn main() {
+  let listener = interact!(bind, "0.0.0.0:0").await.unwrap();
+  let port = listener.local_addr().unwrap().port();
+  // Make the target connect back to `port`...
+  let _ = listener.accept(Some(Duration::from_secs(30))).await.unwrap();
+  # }
+  ```
+- `tls` (requires the `tls` feature; see `tls::Builder` for more options)
+  ```ignore
+  use libspl::interact;
+
+  # #[tokio::main]
+  # async fn main() {
+  let _ = interact!(tls, "www.example.com:443").await.unwrap();
+  # }
+  ```
+- `udp` (requires the `udp` feature)
+  ```ignore
+  use libspl::interact;
+
+  # #[tokio::main]
+  # async fn main() {
+  let _ = interact!(udp, "www.example.com:53").await.unwrap();
+  # }
+  ```
+- `unix` (requires the `unix` feature)
+  ```ignore
+  use libspl::interact;
+
+  # #[tokio::main]
+  # async fn main() {
+  let _ = interact!(unix, "/run/challenge.sock").await.unwrap();
+  # }
+  ```
+- [`transcript`] (see [`Replay`](transcript::Replay))
+  ```no_run
+  use libspl::interact;
+
+  # #[tokio::main]
+  # async fn main() {
+  let _ = interact!(transcript, "/path/to/transcript.jsonl").await.unwrap();
+  # }
+  ```
 */
 #[macro_export]
 macro_rules! interact {
+    ($method: ident$(, $argument: expr)*; $($setting: ident = $value: expr),+ $(,)?) => {
+        async {
+            interact!($method$(, $argument)*).await.map(|mut interaction| {
+                $( interact!(@set interaction, $setting, $value); )+
+                interaction
+            })
+        }
+    };
+    (@set $interaction: ident, timeout, $value: expr) => {
+        ::libspl::Interaction::set_timeout(&mut $interaction, $value)
+    };
+    (@set $interaction: ident, repeat, $value: expr) => {
+        ::libspl::Interaction::set_repeat(&mut $interaction, $value)
+    };
     (stdio, $path: expr) => {
         ::libspl::interaction::stdio::interact::<[&str; 0]>($path, None)
     };
     (stdio, $path: expr$(, $argument: expr)+) => {
         interact!(@internal stdio, $path, Some([$( $argument ),+]))
     };
+    (pty, $path: expr) => {
+        ::libspl::interaction::stdio::interact_pty::<[&str; 0]>($path, None, Default::default())
+    };
+    (pty, $path: expr$(, $argument: expr)+) => {
+        ::libspl::interaction::stdio::interact_pty(
+            $path,
+            Some([$( $argument ),+]),
+            Default::default(),
+        )
+    };
+    (bind, $address: expr) => {
+        ::libspl::interaction::tcp::Listener::bind($address)
+    };
+    (listen, $address: expr) => {
+        ::libspl::interaction::tcp::listen($address, None)
+    };
+    (listen, $address: expr, $timeout: expr) => {
+        ::libspl::interaction::tcp::listen($address, Some($timeout))
+    };
     ($method: ident$(, $argument: expr )*) => {
         interact!(@internal $method$(, $argument)*)
     };
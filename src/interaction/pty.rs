@@ -0,0 +1,192 @@
+/*!
+Pseudo-terminal settings for processes that should behave as if they were run in a terminal.
+
+Programs that write to a pipe usually buffer their output in large blocks, so nothing is received
+until the buffer fills up or the program exits. When connected to a pseudo-terminal instead,
+`isatty` is true and output is line-buffered, just like in an interactive session. See
+[`stdio::interact_pty`](super::stdio::interact_pty) and
+[`ssh::interact_pty`](super::ssh::interact_pty).
+*/
+
+/// How the pseudo-terminal's line discipline treats the data that passes through it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pty {
+    /**
+    Passes every byte through unmodified. Otherwise, input is processed line by line and control
+    characters (e.g. `^C`, `^D`, backspace) are interpreted, and `\n` is translated to `\r\n` in
+    output.
+    */
+    pub raw: bool,
+    /// Echoes input back as output.
+    pub echo: bool,
+}
+impl Pty {
+    /// Raw mode without echo. This is the default, and what exploits usually want.
+    pub const RAW: Self = Self {
+        raw: true,
+        echo: false,
+    };
+    /// Cooked mode with echo, as in an interactive shell.
+    pub const COOKED: Self = Self {
+        raw: false,
+        echo: true,
+    };
+
+    /// The `stty` arguments that apply these settings.
+    #[cfg(feature = "ssh")]
+    pub(super) fn stty(&self) -> String {
+        format!(
+            "{} {}",
+            if self.raw { "raw" } else { "cooked" },
+            if self.echo { "echo" } else { "-echo" }
+        )
+    }
+}
+impl Default for Pty {
+    fn default() -> Self {
+        Self::RAW
+    }
+}
+
+#[cfg(all(unix, feature = "stdio"))]
+pub(super) use master::{Master, claim, open};
+
+#[cfg(all(unix, feature = "stdio"))]
+mod master {
+    use super::Pty;
+    use std::{
+        io,
+        mem::MaybeUninit,
+        os::fd::{AsRawFd, FromRawFd, OwnedFd},
+        pin::Pin,
+        ptr,
+        task::{Context, Poll, ready},
+    };
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf, unix::AsyncFd};
+
+    /// Converts the return value of a libc call into a [`Result`](io::Result).
+    fn check(result: libc::c_int) -> io::Result<libc::c_int> {
+        if result == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(result)
+        }
+    }
+
+    /**
+    Opens a new pseudo-terminal with the given settings, returning its (non-blocking) master side
+    and its slave side.
+    */
+    pub fn open(settings: Pty) -> io::Result<(Master, OwnedFd)> {
+        let (mut master, mut slave) = (0, 0);
+        check(unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                ptr::null_mut(),
+                ptr::null(),
+                ptr::null(),
+            )
+        })?;
+        let (master, slave) =
+            unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+        // Neither side should leak into child processes (other than as their stdio).
+        for fd in [&master, &slave] {
+            check(unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) })?;
+        }
+
+        let mut termios = MaybeUninit::uninit();
+        check(unsafe { libc::tcgetattr(slave.as_raw_fd(), termios.as_mut_ptr()) })?;
+        let mut termios = unsafe { termios.assume_init() };
+        if settings.raw {
+            unsafe { libc::cfmakeraw(&mut termios) };
+        }
+        if settings.echo {
+            termios.c_lflag |= libc::ECHO;
+        } else {
+            termios.c_lflag &= !libc::ECHO;
+        }
+        check(unsafe { libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios) })?;
+
+        let flags = check(unsafe { libc::fcntl(master.as_raw_fd(), libc::F_GETFL) })?;
+        check(unsafe { libc::fcntl(master.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK) })?;
+        Ok((Master(AsyncFd::new(master)?), slave))
+    }
+
+    /**
+    Makes the calling process the leader of a new session, with the pseudo-terminal on its stdin as
+    the controlling terminal. Meant to be run in a freshly forked child.
+    */
+    pub fn claim() -> io::Result<()> {
+        check(unsafe { libc::setsid() })?;
+        check(unsafe { libc::ioctl(0, libc::TIOCSCTTY as _, 0) })?;
+        Ok(())
+    }
+
+    /// The master side of a pseudo-terminal.
+    #[derive(Debug)]
+    pub struct Master(AsyncFd<OwnedFd>);
+    impl AsyncRead for Master {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            loop {
+                let mut guard = ready!(self.0.poll_read_ready(cx))?;
+                let unfilled = buf.initialize_unfilled();
+                match guard.try_io(|fd| {
+                    let n = unsafe {
+                        libc::read(fd.as_raw_fd(), unfilled.as_mut_ptr().cast(), unfilled.len())
+                    };
+                    if n == -1 {
+                        Err(io::Error::last_os_error())
+                    } else {
+                        Ok(n as usize)
+                    }
+                }) {
+                    Ok(Ok(n)) => {
+                        buf.advance(n);
+                        return Poll::Ready(Ok(()));
+                    }
+                    // Once every handle to the slave side is closed, reads fail with EIO.
+                    Ok(Err(error)) if error.raw_os_error() == Some(libc::EIO) => {
+                        return Poll::Ready(Ok(()));
+                    }
+                    Ok(Err(error)) => return Poll::Ready(Err(error)),
+                    Err(_) => continue,
+                }
+            }
+        }
+    }
+    impl AsyncWrite for Master {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<Result<usize, io::Error>> {
+            loop {
+                let mut guard = ready!(self.0.poll_write_ready(cx))?;
+                match guard.try_io(|fd| {
+                    let n = unsafe { libc::write(fd.as_raw_fd(), buf.as_ptr().cast(), buf.len()) };
+                    if n == -1 {
+                        Err(io::Error::last_os_error())
+                    } else {
+                        Ok(n as usize)
+                    }
+                }) {
+                    Ok(result) => return Poll::Ready(result),
+                    Err(_) => continue,
+                }
+            }
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+}
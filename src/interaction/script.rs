@@ -0,0 +1,237 @@
+/*!
+Scripted conversations that can be executed against any [`Interaction`].
+
+Scripts can be built in code from a list of [`Step`]s, or parsed from text with one step per line:
+```text
+# Lines starting with `#` (and blank lines) are ignored.
+expect Your choice \(1-4\):
+sendline 1
+capture canary Canary: 0x([0-9a-f]+)\n
+send AAAA\x00\xff
+sleep 250ms
+pause
+interactive
+```
+The argument to `send`/`sendline` is everything after the first space, with the escape sequences
+`\n`, `\r`, `\t`, `\0`, `\\` and `\xHH` replaced by the bytes they represent. The argument to
+`expect` and the second argument to `capture` are byte [regexes](Regex). `sleep` accepts a number
+followed by `us`, `ms` or `s`.
+*/
+
+use super::{Interaction, PID, Regex, announce_pid, inherit, wait_for_enter};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+use tokio::{io::AsyncWriteExt, time::sleep};
+
+/// One step of a [`Script`].
+#[derive(Clone, Debug)]
+pub enum Step {
+    /// Reads until the pattern matches (see [`expect`](Interaction::expect)).
+    Expect(Regex),
+    /// Writes the bytes to the remote stream.
+    Send(Vec<u8>),
+    /// Writes the bytes and a newline to the remote stream.
+    SendLine(Vec<u8>),
+    /// Waits for the duration.
+    Sleep(Duration),
+    /**
+    Reads until the pattern matches, then stores its first capture group (or the whole match, if it
+    has no capture groups) under the name.
+    */
+    Capture(String, Regex),
+    /**
+    Waits for user confirmation. When run with [`run_with_pid`](Script::run_with_pid), the PID is
+    leaked first (see [`leak_pid`](PID::leak_pid)).
+    */
+    Pause,
+    /// Connects the [`Interaction`] to stdin/stdout (see [`inherit`](Interaction::inherit)).
+    Interactive,
+}
+
+/// The values stored by [`Capture`](Step::Capture) steps, by name.
+pub type Captures = HashMap<String, Vec<u8>>;
+
+/// A sequence of [`Step`]s. See the [module documentation](self).
+#[derive(Clone, Debug, Default)]
+pub struct Script {
+    pub steps: Vec<Step>,
+}
+impl Script {
+    /// Parses the script stored in the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(fs::read_to_string(path)?.parse()?)
+    }
+
+    /// Executes the script against `interaction`.
+    pub async fn run<I: Interaction>(
+        &self,
+        interaction: &mut I,
+    ) -> Result<Captures, Box<dyn Error + Send + Sync>> {
+        self.execute(interaction, None).await
+    }
+
+    /// Like [`run`](Script::run), but leaks the PID of `interaction` on each [`Pause`](Step::Pause).
+    pub async fn run_with_pid<I: PID>(
+        &self,
+        interaction: &mut I,
+    ) -> Result<Captures, Box<dyn Error + Send + Sync>> {
+        let pid = if self.steps.iter().any(|step| matches!(step, Step::Pause)) {
+            Some(interaction.get_pid().await)
+        } else {
+            None
+        };
+        self.execute(interaction, pid.as_ref()).await
+    }
+
+    async fn execute<I: Interaction>(
+        &self,
+        interaction: &mut I,
+        pid: Option<&Result<u32, Box<dyn Error + Send + Sync>>>,
+    ) -> Result<Captures, Box<dyn Error + Send + Sync>> {
+        let mut captures = Captures::new();
+        for step in &self.steps {
+            match step {
+                Step::Expect(pattern) => {
+                    interaction
+                        .expect(std::slice::from_ref(pattern), None)
+                        .await?;
+                }
+                Step::Send(data) => {
+                    interaction.write_all(data).await?;
+                    interaction.flush().await?;
+                }
+                Step::SendLine(data) => interaction.send_line(data).await?,
+                Step::Sleep(duration) => sleep(*duration).await,
+                Step::Capture(name, pattern) => {
+                    let expected = interaction
+                        .expect(std::slice::from_ref(pattern), None)
+                        .await?;
+                    let value = expected.group(1).unwrap_or(expected.matched()).to_vec();
+                    captures.insert(name.clone(), value);
+                }
+                Step::Pause => {
+                    if let Some(pid) = pid {
+                        announce_pid(pid);
+                    }
+                    wait_for_enter().await;
+                }
+                Step::Interactive => inherit(&mut *interaction).await?,
+            }
+        }
+        Ok(captures)
+    }
+}
+impl From<Vec<Step>> for Script {
+    fn from(steps: Vec<Step>) -> Self {
+        Self { steps }
+    }
+}
+impl FromStr for Script {
+    type Err = ParseError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let mut steps = Vec::new();
+        for (index, line) in string.lines().enumerate() {
+            let error = |message: String| ParseError {
+                line: index + 1,
+                message,
+            };
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+
+            let (keyword, rest) = match line.trim_start().split_once(' ') {
+                Some((keyword, rest)) => (keyword, Some(rest)),
+                None => (line.trim(), None),
+            };
+            let argument = || rest.ok_or_else(|| error(format!("`{keyword}` needs an argument")));
+            let regex = |pattern: &str| Regex::new(pattern).map_err(|e| error(e.to_string()));
+            steps.push(match keyword {
+                "expect" => Step::Expect(regex(argument()?)?),
+                "send" => Step::Send(unescape(argument()?).map_err(error)?),
+                "sendline" => Step::SendLine(unescape(argument()?).map_err(error)?),
+                "sleep" => Step::Sleep(parse_duration(argument()?.trim()).map_err(error)?),
+                "capture" => {
+                    let (name, pattern) = argument()?
+                        .split_once(' ')
+                        .ok_or_else(|| error("`capture` needs a name and a pattern".into()))?;
+                    Step::Capture(name.to_owned(), regex(pattern)?)
+                }
+                "pause" => Step::Pause,
+                "interactive" => Step::Interactive,
+                _ => return Err(error(format!("unknown step `{keyword}`"))),
+            });
+        }
+        Ok(Self { steps })
+    }
+}
+
+/// Returned when a [`Script`] cannot be parsed.
+#[derive(Debug)]
+pub struct ParseError {
+    /// The (1-based) line on which the error occurred.
+    pub line: usize,
+    pub message: String,
+}
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+impl Error for ParseError {}
+
+/// Replaces the escape sequences described in the [module documentation](self).
+fn unescape(string: &str) -> Result<Vec<u8>, String> {
+    let mut r = Vec::new();
+    let mut bytes = string.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte != b'\\' {
+            r.push(byte);
+            continue;
+        }
+        r.push(match bytes.next() {
+            Some(b'n') => b'\n',
+            Some(b'r') => b'\r',
+            Some(b't') => b'\t',
+            Some(b'0') => 0,
+            Some(b'\\') => b'\\',
+            Some(b'x') => {
+                let hex = [bytes.next(), bytes.next()];
+                match hex {
+                    [Some(a), Some(b)] => u8::from_str_radix(&String::from_utf8_lossy(&[a, b]), 16)
+                        .map_err(|_| {
+                            format!("invalid escape sequence `\\x{}{}`", a as char, b as char)
+                        })?,
+                    _ => return Err("incomplete escape sequence `\\x`".into()),
+                }
+            }
+            Some(other) => return Err(format!("invalid escape sequence `\\{}`", other as char)),
+            None => return Err("trailing `\\`".into()),
+        });
+    }
+    Ok(r)
+}
+
+/// Parses a duration such as `250ms`, `1.5s` or `100us`.
+fn parse_duration(string: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration `{string}`");
+    let (number, scale) = if let Some(number) = string.strip_suffix("us") {
+        (number, 1e-6)
+    } else if let Some(number) = string.strip_suffix("ms") {
+        (number, 1e-3)
+    } else if let Some(number) = string.strip_suffix('s') {
+        (number, 1.)
+    } else {
+        return Err(invalid());
+    };
+    Duration::try_from_secs_f64(number.parse::<f64>().map_err(|_| invalid())? * scale)
+        .map_err(|_| invalid())
+}
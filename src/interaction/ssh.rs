@@ -1,40 +1,100 @@
 #![cfg(feature = "ssh")]
 
-use super::{Interaction, PID};
+#[cfg(feature = "tcp")]
+use super::tcp::Tcp;
+use super::{Interaction, PID, Timing, pty::Pty};
 use openssh::{Child, Stdio};
-pub use openssh::{KnownHosts, Session};
+pub use openssh::{KnownHosts, Session, SessionBuilder};
 use std::{
     error::Error,
+    fs::Permissions,
     io,
-    path::PathBuf,
+    ops::Deref,
+    os::unix::fs::PermissionsExt,
+    path::Path,
     pin::Pin,
     process::Output,
-    str::FromStr,
+    sync::Arc,
     task::{Context, Poll},
     time::Duration,
 };
-use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+#[cfg(feature = "tcp")]
+use std::{net::SocketAddr, ops::DerefMut};
+use tokio::{
+    fs,
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf},
+    sync::OnceCell,
+};
 
-#[ouroboros::self_referencing]
+/**
+A process on a remote host as an [`Interaction`]. See [`interact`] and [`Connection::spawn`].
+*/
+#[derive(Debug)]
 pub struct SSH {
-    session: Session,
-
-    #[borrows(session)]
-    #[covariant]
-    pub process: Child<&'this Session>,
-
-    name: String,
+    connection: Connection,
+    pub process: Child<Arc<Session>>,
+    /// The PID the remote shell reported at launch.
+    pid: u32,
+    /**
+    The process group the remote shell reported at launch, which it only leads if the remote user's
+    shell replaced itself with it.
+    */
+    group: u32,
+    name: Option<String>,
+    timing: Timing,
 }
 impl SSH {
-    async fn is_linux(session: &Session) -> bool {
-        match session.command("uname").output().await {
-            Ok(Output { ref stdout, .. })
-                if std::str::from_utf8(stdout).is_ok_and(|string| string.contains("Linux")) =>
-            {
-                true
-            }
-            _ => false,
-        }
+    /// The connection this process runs over, which can be used to spawn more.
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /**
+    Makes [`get_pid`](PID::get_pid) look up the newest process named `name` in the remote
+    process's group, rather than returning the PID reported at launch. Useful when the target is
+    not what replaces the remote shell (see [`interact`]), e.g. when `file` runs a wrapper script.
+    Requires `pgrep` on the remote host.
+    */
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = Some(name.into());
+    }
+
+    /**
+    Connects to `host` (as resolved by the remote host) on `port` through this session. See
+    [`forward`].
+    */
+    #[cfg(feature = "tcp")]
+    pub async fn forward(
+        &self,
+        host: impl AsRef<str>,
+        port: u16,
+    ) -> Result<Forward, Box<dyn Error + Send + Sync>> {
+        forward(&self.connection, host, port).await
+    }
+
+    /**
+    Copies the file at `local` to `remote` on the remote host, over the same session as the
+    remote process. The remote file is created (or truncated) with the same permissions as the
+    local one.
+    */
+    pub async fn upload(
+        &self,
+        local: impl AsRef<Path>,
+        remote: impl AsRef<str>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        upload(&self.connection, local, remote).await
+    }
+
+    /**
+    Copies the file at `remote` on the remote host to `local`, over the same session as the remote
+    process. The local file is created (or truncated) with the same permissions as the remote one.
+    */
+    pub async fn download(
+        &self,
+        remote: impl AsRef<str>,
+        local: impl AsRef<Path>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        download(&self.connection, remote, local).await
     }
 }
 impl AsyncRead for SSH {
@@ -43,9 +103,7 @@ impl AsyncRead for SSH {
         cx: &mut Context<'_>,
         buf: &mut ReadBuf<'_>,
     ) -> Poll<io::Result<()>> {
-        self.with_process_mut(|process| {
-            Pin::new(process.stdout().as_mut().unwrap()).poll_read(cx, buf)
-        })
+        Pin::new(self.process.stdout().as_mut().unwrap()).poll_read(cx, buf)
     }
 }
 impl AsyncWrite for SSH {
@@ -54,121 +112,657 @@ impl AsyncWrite for SSH {
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<Result<usize, io::Error>> {
-        self.with_process_mut(|process| {
-            Pin::new(process.stdin().as_mut().unwrap()).poll_write(cx, buf)
-        })
+        Pin::new(self.process.stdin().as_mut().unwrap()).poll_write(cx, buf)
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
-        self.with_process_mut(|process| Pin::new(process.stdin().as_mut().unwrap()).poll_flush(cx))
+        Pin::new(self.process.stdin().as_mut().unwrap()).poll_flush(cx)
     }
 
     fn poll_shutdown(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Result<(), io::Error>> {
-        self.with_process_mut(|process| {
-            Pin::new(process.stdin().as_mut().unwrap()).poll_shutdown(cx)
-        })
+        Pin::new(self.process.stdin().as_mut().unwrap()).poll_shutdown(cx)
     }
 }
 impl Interaction for SSH {
     const TIMEOUT: Duration = Duration::from_millis(50);
     const REPEAT: usize = 3;
 
+    /**
+    Kills the remote process's whole process group, then [closes](Connection::close) its handle to
+    the connection.
+    */
     async fn close(self) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let pid = self.get_pid().await;
-        let session = self.into_heads().session;
-        if let Ok(pid) = pid {
-            session
-                .command("kill")
-                .args(["-9", &pid.to_string()])
-                .output()
-                .await?;
+        let Self {
+            connection,
+            process,
+            pid,
+            group,
+            ..
+        } = self;
+        // The process holds a handle to the session too.
+        drop(process);
+        connection
+            .command("sh")
+            .args([
+                "-c",
+                r#"kill -9 "-$1" 2>/dev/null || kill -9 "$2""#,
+                "sh",
+                &group.to_string(),
+                &pid.to_string(),
+            ])
+            .output()
+            .await?;
+        Ok(connection.close().await?)
+    }
+
+    fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    fn timing_mut(&mut self) -> &mut Timing {
+        &mut self.timing
+    }
+}
+
+/**
+A shareable handle to an SSH [session](Session), which can [spawn](Connection::spawn) any number of
+remote processes and [run](Connection::run) one-off commands without connecting again.
+```no_run
+use libspl::{Interaction, interaction::ssh};
+
+# use std::error::Error;
+# #[tokio::main]
+# async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+let connection = ssh::connect("challenge.example.com").await?;
+for guess in 0..256 {
+    let mut interaction = connection.spawn("./vuln").await?;
+    interaction.send_line(&[guess as u8]).await?;
+    interaction.close().await?;
+}
+connection.close().await?;
+# Ok(())
+# }
+```
+*/
+#[derive(Clone, Debug)]
+pub struct Connection {
+    session: Arc<Session>,
+    /// Whether the remote host runs Linux, which is only checked once per session.
+    linux: Arc<OnceCell<bool>>,
+}
+impl Connection {
+    /// Failures to run `uname` are not cached, so that the next launch asks again.
+    async fn is_linux(&self) -> Result<bool, openssh::Error> {
+        self.linux
+            .get_or_try_init(|| async {
+                let Output { stdout, .. } = self.command("uname").output().await?;
+                Ok(std::str::from_utf8(&stdout).is_ok_and(|string| string.contains("Linux")))
+            })
+            .await
+            .copied()
+    }
+
+    /**
+    Launches `file` on the remote host and returns an [`Interaction`] connected to it. See
+    [`interact`].
+    */
+    pub async fn spawn(&self, file: impl AsRef<str>) -> Result<SSH, Box<dyn Error + Send + Sync>> {
+        spawn(self.clone(), file, None).await
+    }
+
+    /**
+    Launches `file` in a [pseudo-terminal](super::pty) on the remote host. See [`interact_pty`].
+    */
+    pub async fn spawn_pty(
+        &self,
+        file: impl AsRef<str>,
+        settings: Pty,
+    ) -> Result<SSH, Box<dyn Error + Send + Sync>> {
+        spawn(self.clone(), file, Some(settings)).await
+    }
+
+    /**
+    Runs `command` with the remote user's shell and waits for it to finish, collecting its output
+    (whether or not it succeeds).
+    */
+    pub async fn run(&self, command: impl AsRef<str>) -> Result<Output, openssh::Error> {
+        self.raw_command(command.as_ref()).output().await
+    }
+
+    /// See [`forward`].
+    #[cfg(feature = "tcp")]
+    pub async fn forward(
+        &self,
+        host: impl AsRef<str>,
+        port: u16,
+    ) -> Result<Forward, Box<dyn Error + Send + Sync>> {
+        forward(self, host, port).await
+    }
+
+    /// See [`SSH::upload`].
+    pub async fn upload(
+        &self,
+        local: impl AsRef<Path>,
+        remote: impl AsRef<str>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        upload(self, local, remote).await
+    }
+
+    /// See [`SSH::download`].
+    pub async fn download(
+        &self,
+        remote: impl AsRef<str>,
+        local: impl AsRef<Path>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        download(self, remote, local).await
+    }
+
+    /**
+    Closes this handle. The session itself is closed along with the last handle, so processes
+    spawned from it keep running until they are [closed](Interaction::close) too.
+    */
+    pub async fn close(self) -> Result<(), openssh::Error> {
+        match Arc::try_unwrap(self.session) {
+            Ok(session) => session.close().await,
+            Err(_) => Ok(()),
+        }
+    }
+}
+impl Deref for Connection {
+    type Target = Session;
+
+    fn deref(&self) -> &Self::Target {
+        &self.session
+    }
+}
+impl From<Session> for Connection {
+    fn from(session: Session) -> Self {
+        Self {
+            session: Arc::new(session),
+            linux: Arc::new(OnceCell::new()),
         }
-        session.close().await?;
-        Ok(())
     }
 }
 
+/**
+Configures SSH [sessions](Session) and opens [interactions](Interaction) over them. Options that are
+not set here are taken from the SSH configuration (e.g. `~/.ssh/config`), as usual.
+```no_run
+use libspl::interaction::ssh::Builder;
+use std::time::Duration;
+
+# use std::error::Error;
+# #[tokio::main]
+# async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+let interaction = Builder::new()
+    .insecure()
+    .user("ctf")
+    .port(2222)
+    .identity("./id_ed25519")
+    .connect_timeout(Duration::from_secs(5))
+    .interact("challenge.example.com", "./vuln")
+    .await?;
+# Ok(())
+# }
+```
+*/
+#[derive(Clone, Debug)]
+pub struct Builder {
+    session: SessionBuilder,
+    jump_hosts: Vec<String>,
+}
+impl Builder {
+    /// Only connects to hosts whose keys are already known, like [`interact`].
+    pub fn new() -> Self {
+        let mut session = SessionBuilder::default();
+        session.known_hosts_check(KnownHosts::Strict);
+        session.into()
+    }
+
+    /// Sets how the host's key is checked against the known hosts.
+    pub fn known_hosts(mut self, policy: KnownHosts) -> Self {
+        self.session.known_hosts_check(policy);
+        self
+    }
+
+    /**
+    Reads (and, depending on the [policy](Builder::known_hosts), adds) known host keys at `path`.
+    */
+    pub fn known_hosts_file(mut self, path: impl AsRef<Path>) -> Self {
+        self.session.user_known_hosts_file(path);
+        self
+    }
+
+    /**
+    Accepts any host key, without reading or writing the known hosts. Meant for hosts with
+    throwaway keys.
+    */
+    pub fn insecure(self) -> Self {
+        self.known_hosts(KnownHosts::Accept)
+            .known_hosts_file("/dev/null")
+    }
+
+    /// Sets the port to connect to.
+    pub fn port(mut self, port: u16) -> Self {
+        self.session.port(port);
+        self
+    }
+
+    /// Sets the user to log in as.
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.session.user(user.into());
+        self
+    }
+
+    /// Authenticates with the private key at `path`.
+    pub fn identity(mut self, path: impl AsRef<Path>) -> Self {
+        self.session.keyfile(path);
+        self
+    }
+
+    /**
+    Connects through the jump host `host` (`[user@]host[:port]`). Multiple jump hosts are passed
+    through in the order they were added. Options set here do not apply to jump hosts.
+    */
+    pub fn jump_host(mut self, host: impl Into<String>) -> Self {
+        self.jump_hosts.push(host.into());
+        self
+    }
+
+    /// Gives up on connecting after `timeout` (rounded down to whole seconds).
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.session.connect_timeout(timeout);
+        self
+    }
+
+    /**
+    Creates the connection's control socket in `directory`, rather than the user's state directory
+    (e.g. `~/.local/state`).
+    */
+    pub fn control_dir(mut self, directory: impl AsRef<Path>) -> Self {
+        self.session.control_directory(directory);
+        self
+    }
+
+    /// Creates a new SSH [session](Session) with the host at `url`.
+    pub async fn connect(mut self, url: impl AsRef<str>) -> Result<Session, openssh::Error> {
+        self.session.jump_hosts(&self.jump_hosts);
+        self.session.connect_mux(url).await
+    }
+
+    /// Like [`interact`], but with the [session](Session) configured by this builder.
+    pub async fn interact(
+        self,
+        url: impl AsRef<str>,
+        file: impl AsRef<str>,
+    ) -> Result<SSH, Box<dyn Error + Send + Sync>> {
+        Connection::from(self.connect(url).await?).spawn(file).await
+    }
+
+    /// Like [`interact_pty`], but with the [session](Session) configured by this builder.
+    pub async fn interact_pty(
+        self,
+        url: impl AsRef<str>,
+        file: impl AsRef<str>,
+        settings: Pty,
+    ) -> Result<SSH, Box<dyn Error + Send + Sync>> {
+        Connection::from(self.connect(url).await?)
+            .spawn_pty(file, settings)
+            .await
+    }
+}
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+/// Starts from an existing [`SessionBuilder`], for options this builder doesn't cover.
+impl From<SessionBuilder> for Builder {
+    fn from(session: SessionBuilder) -> Self {
+        Self {
+            session,
+            jump_hosts: Vec::new(),
+        }
+    }
+}
+
+/**
+Creates a new SSH [session](Session) with the host at `url`, as a shareable [`Connection`]. Only
+hosts whose keys are already known are accepted. See [`Builder`] for more options.
+*/
+pub async fn connect(url: impl AsRef<str>) -> Result<Connection, openssh::Error> {
+    Ok(Builder::new().connect(url).await?.into())
+}
+
 /**
 Creates a new SSH [session](Session) with the host at `url`. Then, launches `file` on the remote
 host and returns an [`Interaction`] connected to that remote process.
 
 Before launching `file`, this function will attempt to run `uname` on the remote system to detect if
 it is running Linux. If so, `file` with be run with the command prefix `"stdbuf -o0 "` to avoid
-Linux buffering/withholding remote program output.
+Linux buffering/withholding remote program output. [`interact_pty`] avoids the buffering without
+relying on `stdbuf`.
+
+`file` is run as is by a shell that first reports its PID and process group after a marker line
+(which is read before this function returns, along with anything the remote user's shell prints
+before it), so the remote shell must be POSIX-compatible. Closing the [`SSH`] kills that whole
+group. Most shells start the last command of `file` as a child process, so to get the PID of the
+target itself, replace the shell with it (e.g. `"exec ./vuln"`), or see [`SSH::set_name`].
+
+Only hosts whose keys are already known are accepted. See [`Builder`] for more options.
 */
 pub async fn interact(
     url: impl AsRef<str>,
     file: impl AsRef<str>,
 ) -> Result<SSH, Box<dyn Error + Send + Sync>> {
-    Ok(SSHAsyncSendTryBuilder {
-        session: Session::connect_mux(url, KnownHosts::Strict).await?,
-        process_builder: |session: &Session| {
-            let mut shell = String::from(file.as_ref());
-            Box::pin(async move {
-                if SSH::is_linux(session).await {
-                    shell.insert_str(0, "stdbuf -o0 sh -c '\n");
-                    shell += "\n'";
-                }
-                session
-                    .shell(shell)
-                    .stdout(Stdio::piped())
-                    .stdin(Stdio::piped())
-                    .spawn()
-                    .await
-            })
-        },
-        name: {
-            if let Some(name) = PathBuf::from_str(
-                file.as_ref()
-                    .trim_matches('\'')
-                    .trim()
-                    .replace("\n", ";")
-                    .replace("\\;", ";")
-                    .split(';')
-                    .last()
-                    .unwrap()
-                    .trim()
-                    .trim_start_matches(['.', '/'])
-                    .split(" ")
-                    .next()
-                    .unwrap(),
-            )?
-            .file_name()
-            {
-                name.to_string_lossy().to_string()
-            } else {
-                return Err(Box::new(io::Error::from(io::ErrorKind::InvalidFilename)));
-            }
-        },
-    }
-    .try_build()
-    .await?)
+    Builder::new().interact(url, file).await
+}
+
+/**
+Like [`interact`], but `file` is run in a [pseudo-terminal](super::pty) on the remote host, with
+the given settings.
+
+The pseudo-terminal is allocated by running `file` through the remote host's `script` utility (which
+is part of util-linux, BusyBox and the BSDs), since the SSH multiplexing connection cannot request
+one itself. The settings are applied with `stty` just before `file` starts, so anything sent before
+then is subject to the remote host's default settings (usually cooked mode with echo).
+*/
+pub async fn interact_pty(
+    url: impl AsRef<str>,
+    file: impl AsRef<str>,
+    settings: Pty,
+) -> Result<SSH, Box<dyn Error + Send + Sync>> {
+    Builder::new().interact_pty(url, file, settings).await
+}
+
+/**
+Connects to `host` (as resolved by the remote host) on `port` through `connection`, and returns the
+connection as a [TCP interaction](Forward). This reaches services that are only accessible from the
+remote host, like `ssh -L` does.
+
+The forward listens on an ephemeral local port until the returned interaction is
+[closed](Interaction::close). If another process takes the port before the forward does, a new one
+is tried.
+*/
+#[cfg(feature = "tcp")]
+pub async fn forward(
+    connection: &Connection,
+    host: impl AsRef<str>,
+    port: u16,
+) -> Result<Forward, Box<dyn Error + Send + Sync>> {
+    use openssh::{ForwardType, Socket};
+    use std::net::Ipv4Addr;
+    use tokio::net::{TcpListener, TcpStream};
+
+    const ATTEMPTS: usize = 3;
+
+    let remote = Socket::TcpSocket {
+        host: host.as_ref().to_owned().into(),
+        port,
+    };
+    let mut attempt = 1;
+    // The port is only free until the listener is dropped.
+    let local = loop {
+        let local = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await?
+            .local_addr()?;
+        match connection
+            .request_port_forward(ForwardType::Local, local, remote.clone())
+            .await
+        {
+            Ok(()) => break local,
+            Err(_) if attempt < ATTEMPTS => attempt += 1,
+            Err(error) => return Err(error.into()),
+        }
+    };
+    Ok(Forward {
+        tcp: TcpStream::connect(local).await?.into(),
+        connection: connection.clone(),
+        local,
+        remote,
+    })
+}
+
+/**
+A connection through an SSH [port forward](forward), which is a [TCP interaction](Tcp) that also
+closes the forward (and its handle to the [`Connection`]) when [closed](Interaction::close).
+*/
+#[cfg(feature = "tcp")]
+#[derive(Debug)]
+pub struct Forward {
+    tcp: Tcp,
+    connection: Connection,
+    local: SocketAddr,
+    remote: openssh::Socket<'static>,
+}
+#[cfg(feature = "tcp")]
+impl Deref for Forward {
+    type Target = Tcp;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tcp
+    }
+}
+#[cfg(feature = "tcp")]
+impl DerefMut for Forward {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.tcp
+    }
+}
+#[cfg(feature = "tcp")]
+impl AsyncRead for Forward {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.tcp).poll_read(cx, buf)
+    }
+}
+#[cfg(feature = "tcp")]
+impl AsyncWrite for Forward {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        Pin::new(&mut self.tcp).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.tcp).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.tcp).poll_shutdown(cx)
+    }
+}
+#[cfg(feature = "tcp")]
+impl Interaction for Forward {
+    const TIMEOUT: Duration = Tcp::TIMEOUT;
+    const REPEAT: usize = Tcp::REPEAT;
+
+    async fn close(self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let Self {
+            tcp,
+            connection,
+            local,
+            remote,
+        } = self;
+        tcp.close().await?;
+        connection
+            .close_port_forward(openssh::ForwardType::Local, local, remote)
+            .await?;
+        Ok(connection.close().await?)
+    }
+
+    fn timing(&self) -> &Timing {
+        self.tcp.timing()
+    }
+
+    fn timing_mut(&mut self) -> &mut Timing {
+        self.tcp.timing_mut()
+    }
+}
+
+/// Precedes the PID and process group the remote shell reports at launch.
+const MARKER: &str = "libspl-pid:";
+
+async fn spawn(
+    connection: Connection,
+    file: impl AsRef<str>,
+    pty: Option<Pty>,
+) -> Result<SSH, Box<dyn Error + Send + Sync>> {
+    // `file` is embedded in single quotes below.
+    let file = file.as_ref().replace('\'', r"'\''");
+    let linux = connection.is_linux().await.unwrap_or(false);
+    // The remote user's shell may run this one as a child, which then does not lead its group.
+    let report = if linux {
+        format!("read -r _ _ _ _ group _ </proc/$$/stat; echo {MARKER}$$ $group")
+    } else {
+        format!("echo {MARKER}$$ $(ps -o pgid= -p $$)")
+    };
+    // `script` runs its command in a new session, so the PID is reported from inside.
+    let shell = match (pty, linux) {
+        (Some(pty), true) => format!(
+            "script -qfc '\nstty {}\n{report}\n{file}\n' /dev/null",
+            pty.stty()
+        ),
+        (Some(pty), false) => format!(
+            "script -q /dev/null sh -c '\nstty {}\n{report}\n{file}\n'",
+            pty.stty()
+        ),
+        (None, true) => format!("{report}; exec stdbuf -o0 sh -c '\n{file}\n'"),
+        (None, false) => format!("{report}; exec sh -c '\n{file}\n'"),
+    };
+    let process = Session::to_command(connection.session.clone(), "sh")
+        .arg("-c")
+        .arg(shell)
+        .stdout(Stdio::piped())
+        .stdin(Stdio::piped())
+        .spawn()
+        .await?;
+    let mut ssh = SSH {
+        connection,
+        process,
+        pid: 0,
+        group: 0,
+        name: None,
+        timing: Timing::of::<SSH>(),
+    };
+
+    // Anything before the marker comes from the remote user's shell (e.g. its rc files).
+    let mut line = Vec::new();
+    let report = loop {
+        match ssh.read_u8().await? {
+            b'\n' => match String::from_utf8_lossy(&line).split_once(MARKER) {
+                Some((_, report)) => break report.to_owned(),
+                None => line.clear(),
+            },
+            byte => line.push(byte),
+        }
+    };
+    let mut report = report.split_whitespace();
+    ssh.pid = report.next().unwrap_or_default().parse()?;
+    // Without a group (e.g. if `ps` is missing), assume the shell leads its own.
+    ssh.group = report.next().map_or(Ok(ssh.pid), str::parse)?;
+    Ok(ssh)
 }
 
 impl PID for SSH {
     async fn get_pid(&self) -> Result<u32, Box<dyn Error + Send + Sync>> {
-        if Self::is_linux(self.borrow_session()).await {
-            let grepout = String::from_utf8(
-                self.borrow_session()
-                    .command("pgrep")
-                    .arg(self.borrow_name())
-                    .output()
-                    .await
-                    .unwrap()
-                    .stdout,
-            )
-            .expect("`pgrep` output from remote host contained non-UTF8 characters");
-            if let Some(pid) = {
-                let mut ids = grepout.split(|b| b == '\n').collect::<Vec<_>>();
-                ids.pop();
-                ids.pop()
-            } {
-                return Ok(pid.parse()?);
-            }
+        let Some(name) = &self.name else {
+            return Ok(self.pid);
+        };
+        let output = self
+            .connection
+            .command("pgrep")
+            .args(["-n", "-g", &self.group.to_string(), name])
+            .output()
+            .await?;
+        // The remote user's shell may print something before `pgrep` does.
+        match std::str::from_utf8(&output.stdout)?.lines().last() {
+            None => Err(Box::new(io::Error::from(io::ErrorKind::NotFound))),
+            Some(pid) => Ok(pid.trim().parse()?),
         }
-        Err(Box::new(io::Error::from(io::ErrorKind::NotFound)))
     }
 }
+
+/// Fails with the remote command's stderr if it did not succeed.
+fn check(output: Output) -> Result<Output, Box<dyn Error + Send + Sync>> {
+    if output.status.success() {
+        Ok(output)
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().into())
+    }
+}
+
+async fn upload(
+    session: &Session,
+    local: impl AsRef<Path>,
+    remote: impl AsRef<str>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let contents = fs::read(&local).await?;
+    let mode = fs::metadata(&local).await?.permissions().mode() & 0o7777;
+    let mut child = session
+        .command("sh")
+        .args([
+            "-c",
+            r#"cat > "$1" && chmod "$2" "$1""#,
+            "sh",
+            remote.as_ref(),
+            &format!("{mode:o}"),
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .await?;
+    let mut stdin = child.stdin().take().unwrap();
+    stdin.write_all(&contents).await?;
+    stdin.shutdown().await?;
+    drop(stdin);
+    check(child.wait_with_output().await?)?;
+    Ok(())
+}
+
+async fn download(
+    session: &Session,
+    remote: impl AsRef<str>,
+    local: impl AsRef<Path>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    /*
+    GNU `stat` takes `-c`, BSD (and macOS) `stat` takes `-f`. If neither works, `cat` reports why
+    the file can't be read, or the file is readable but there's no way to get its mode.
+    */
+    const SCRIPT: &str = r#"
+mode=$(stat -c %a "$1" 2>/dev/null || stat -f %Lp "$1" 2>/dev/null) || {
+    cat "$1" >/dev/null && echo "cannot read the mode of $1" >&2
+    exit 1
+}
+echo "$mode" && cat "$1"
+"#;
+    let output = check(
+        session
+            .command("sh")
+            .args(["-c", SCRIPT, "sh", remote.as_ref()])
+            .output()
+            .await?,
+    )?;
+    let newline = output
+        .stdout
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))?;
+    let (mode, contents) = (&output.stdout[..newline], &output.stdout[newline + 1..]);
+    let mode = u32::from_str_radix(std::str::from_utf8(mode)?.trim(), 8)?;
+    fs::write(&local, contents).await?;
+    fs::set_permissions(&local, Permissions::from_mode(mode)).await?;
+    Ok(())
+}
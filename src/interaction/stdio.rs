@@ -1,12 +1,14 @@
 #![cfg(feature = "stdio")]
 
-use super::{Interaction, PID};
+use super::{Interaction, PID, Timing, pty::Pty};
+#[cfg(unix)]
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
 use std::{
     error::Error,
-    ffi::OsStr,
+    ffi::{OsStr, OsString},
     io,
     ops::{Deref, DerefMut},
-    path::Path,
+    path::{Path, PathBuf},
     pin::Pin,
     process,
     task::{Context, Poll},
@@ -17,17 +19,23 @@ use tokio::{
     process::{Child, Command},
 };
 
-pub struct Stdio(Child);
+#[derive(Debug)]
+pub struct Stdio {
+    child: Child,
+    timing: Timing,
+    #[cfg(unix)]
+    pty: Option<super::pty::Master>,
+}
 impl Deref for Stdio {
     type Target = Child;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.child
     }
 }
 impl DerefMut for Stdio {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.child
     }
 }
 impl AsyncRead for Stdio {
@@ -36,7 +44,11 @@ impl AsyncRead for Stdio {
         cx: &mut Context<'_>,
         buf: &mut ReadBuf<'_>,
     ) -> Poll<io::Result<()>> {
-        Pin::new((&mut **self).stdout.as_mut().unwrap()).poll_read(cx, buf)
+        #[cfg(unix)]
+        if let Some(pty) = &mut self.pty {
+            return Pin::new(pty).poll_read(cx, buf);
+        }
+        Pin::new(self.stdout.as_mut().unwrap()).poll_read(cx, buf)
     }
 }
 impl AsyncWrite for Stdio {
@@ -45,18 +57,30 @@ impl AsyncWrite for Stdio {
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<Result<usize, io::Error>> {
-        Pin::new((&mut **self).stdin.as_mut().unwrap()).poll_write(cx, buf)
+        #[cfg(unix)]
+        if let Some(pty) = &mut self.pty {
+            return Pin::new(pty).poll_write(cx, buf);
+        }
+        Pin::new(self.stdin.as_mut().unwrap()).poll_write(cx, buf)
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
-        Pin::new((&mut **self).stdin.as_mut().unwrap()).poll_flush(cx)
+        #[cfg(unix)]
+        if let Some(pty) = &mut self.pty {
+            return Pin::new(pty).poll_flush(cx);
+        }
+        Pin::new(self.stdin.as_mut().unwrap()).poll_flush(cx)
     }
 
     fn poll_shutdown(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Result<(), io::Error>> {
-        Pin::new((&mut **self).stdin.as_mut().unwrap()).poll_shutdown(cx)
+        #[cfg(unix)]
+        if let Some(pty) = &mut self.pty {
+            return Pin::new(pty).poll_shutdown(cx);
+        }
+        Pin::new(self.stdin.as_mut().unwrap()).poll_shutdown(cx)
     }
 }
 impl Interaction for Stdio {
@@ -66,9 +90,387 @@ impl Interaction for Stdio {
         self.kill().await?;
         Ok(())
     }
+
+    fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    fn timing_mut(&mut self) -> &mut Timing {
+        &mut self.timing
+    }
+}
+
+/// Where a child process's stderr goes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Stderr {
+    /// Shares this process's stderr.
+    #[default]
+    Inherit,
+    /**
+    Goes to the same place as its stdout, so it is read from the [`Stdio`] along with (and in the
+    same order as) everything else. The default for child processes in a
+    [pseudo-terminal](Builder::pty). Only supported on Unix.
+    */
+    Merge,
+    /**
+    Goes to a separate pipe, which can be read from through the child's
+    [`stderr`](tokio::process::Child::stderr) handle.
+    */
+    Separate,
+    /// Is discarded.
+    Discard,
+}
+impl Stderr {
+    /// Configures `command` accordingly.
+    fn apply(self, command: &mut Command) -> io::Result<()> {
+        match self {
+            Self::Inherit => command.stderr(process::Stdio::inherit()),
+            Self::Separate => command.stderr(process::Stdio::piped()),
+            Self::Discard => command.stderr(process::Stdio::null()),
+            #[cfg(unix)]
+            Self::Merge => unsafe {
+                command.pre_exec(|| {
+                    if libc::dup2(1, 2) == -1 {
+                        Err(io::Error::last_os_error())
+                    } else {
+                        Ok(())
+                    }
+                })
+            },
+            #[cfg(not(unix))]
+            Self::Merge => return Err(io::ErrorKind::Unsupported.into()),
+        };
+        Ok(())
+    }
+}
+
+/**
+Configures and launches [child processes](tokio::process::Child) for interaction.
+```no_run
+use libspl::interaction::stdio::{Builder, Stderr};
+
+# use std::error::Error;
+# #[tokio::main]
+# async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+let interaction = Builder::new("./vuln")
+    .env_clear()
+    .env("PWD", "/home/ctf")
+    .arg0("/home/ctf/vuln")
+    .stderr(Stderr::Merge)
+    .spawn()?;
+# Ok(())
+# }
+```
+*/
+#[derive(Debug)]
+pub struct Builder {
+    path: OsString,
+    arguments: Vec<OsString>,
+    /// Variables to set (or remove, if the value is `None`), in order.
+    environment: Vec<(OsString, Option<OsString>)>,
+    clear_environment: bool,
+    current_dir: Option<PathBuf>,
+    /// Where stderr goes, if not where it goes by default.
+    stderr: Option<Stderr>,
+    preload: Vec<OsString>,
+    #[cfg(unix)]
+    arg0: Option<OsString>,
+    /// The loader, and the directory to search for libraries first.
+    #[cfg(unix)]
+    loader: Option<(PathBuf, PathBuf)>,
+    #[cfg(unix)]
+    pty: Option<Pty>,
+    #[cfg(unix)]
+    umask: Option<libc::mode_t>,
+    #[cfg(unix)]
+    fds: Vec<(RawFd, OwnedFd)>,
 }
+impl Builder {
+    /**
+    Runs the executable at `path` without arguments, connected through pipes, with this process's
+    environment, working directory, umask and stderr.
+    */
+    pub fn new(path: impl AsRef<OsStr>) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            arguments: Vec::new(),
+            environment: Vec::new(),
+            clear_environment: false,
+            current_dir: None,
+            stderr: None,
+            preload: Vec::new(),
+            #[cfg(unix)]
+            arg0: None,
+            #[cfg(unix)]
+            loader: None,
+            #[cfg(unix)]
+            pty: None,
+            #[cfg(unix)]
+            umask: None,
+            #[cfg(unix)]
+            fds: Vec::new(),
+        }
+    }
+
+    /// Appends an argument.
+    pub fn arg(mut self, argument: impl AsRef<OsStr>) -> Self {
+        self.arguments.push(argument.as_ref().to_owned());
+        self
+    }
+
+    /// Appends several arguments.
+    pub fn args<I>(mut self, arguments: I) -> Self
+    where
+        I: IntoIterator,
+        <I as IntoIterator>::Item: AsRef<OsStr>,
+    {
+        self.arguments.extend(
+            arguments
+                .into_iter()
+                .map(|argument| argument.as_ref().to_owned()),
+        );
+        self
+    }
+
+    /// Sets an environment variable.
+    pub fn env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+        self.environment
+            .push((key.as_ref().to_owned(), Some(value.as_ref().to_owned())));
+        self
+    }
+
+    /// Sets several environment variables.
+    pub fn envs<I, K, V>(mut self, variables: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        for (key, value) in variables {
+            self = self.env(key, value);
+        }
+        self
+    }
+
+    /// Removes an environment variable.
+    pub fn env_remove(mut self, key: impl AsRef<OsStr>) -> Self {
+        self.environment.push((key.as_ref().to_owned(), None));
+        self
+    }
+
+    /**
+    Starts from an empty environment instead of this process's, discarding any variables set so
+    far.
+    */
+    pub fn env_clear(mut self) -> Self {
+        self.clear_environment = true;
+        self.environment.clear();
+        self
+    }
+
+    /**
+    Adds a shared library to `LD_PRELOAD`. Libraries are loaded in the order they were added, and
+    replace any value of `LD_PRELOAD` set with [`env`](Builder::env).
+    */
+    pub fn preload(mut self, library: impl AsRef<OsStr>) -> Self {
+        self.preload.push(library.as_ref().to_owned());
+        self
+    }
+
+    /// Sets the working directory.
+    pub fn current_dir(mut self, directory: impl AsRef<Path>) -> Self {
+        self.current_dir = Some(directory.as_ref().to_owned());
+        self
+    }
+
+    /**
+    Sets where the child process's stderr goes. By default, it is shared with this process, unless
+    the child process runs in a [pseudo-terminal](Builder::pty).
+    */
+    pub fn stderr(mut self, stderr: Stderr) -> Self {
+        self.stderr = Some(stderr);
+        self
+    }
+
+    /**
+    Connects the child process's stdin and stdout to a new [pseudo-terminal](super::pty) (which
+    also becomes its controlling terminal) instead of pipes. Its stderr is
+    [merged](Stderr::Merge) into the pseudo-terminal too, unless [set](Builder::stderr) otherwise.
+    */
+    #[cfg(unix)]
+    pub fn pty(mut self, settings: Pty) -> Self {
+        self.pty = Some(settings);
+        self
+    }
 
-/// Launch a [child process](tokio::process::Child) for interaction.
+    /**
+    Sets `argv[0]`, which is the path to the executable by default. Together with
+    [`libc`](Builder::libc), this requires the loader to be from glibc 2.33 or later.
+    */
+    #[cfg(unix)]
+    pub fn arg0(mut self, arg0: impl AsRef<OsStr>) -> Self {
+        self.arg0 = Some(arg0.as_ref().to_owned());
+        self
+    }
+
+    /**
+    Runs the executable through the dynamic loader at `loader` (e.g. a bundled
+    `ld-linux-x86-64.so.2`), which searches the directory containing `libc` for shared libraries
+    before any other. Like any other libraries in that directory, `libc` must be named after its
+    soname (usually `libc.so.6`). The executable's path is not looked up in `PATH`.
+
+    `argv[0]` is the same as when the executable is run directly, but `/proc/self/exe` refers to
+    the loader.
+    */
+    #[cfg(unix)]
+    pub fn libc(mut self, libc: impl AsRef<Path>, loader: impl AsRef<Path>) -> Self {
+        let directory = match libc.as_ref().parent() {
+            Some(directory) if !directory.as_os_str().is_empty() => directory,
+            _ => Path::new("."),
+        };
+        self.loader = Some((loader.as_ref().to_owned(), directory.to_owned()));
+        self
+    }
+
+    /// Sets the file mode creation mask.
+    #[cfg(unix)]
+    pub fn umask(mut self, mask: u32) -> Self {
+        self.umask = Some(mask as libc::mode_t);
+        self
+    }
+
+    /**
+    Passes `fd` on to the child process as file descriptor number `target`, replacing whatever
+    would otherwise be open there. Each `target` should only be used once, but may be the number of
+    another file descriptor passed this way.
+    */
+    #[cfg(unix)]
+    pub fn fd(mut self, target: RawFd, fd: impl Into<OwnedFd>) -> Self {
+        self.fds.push((target, fd.into()));
+        self
+    }
+
+    /// Launches the child process.
+    pub fn spawn(self) -> io::Result<Stdio> {
+        #[cfg(unix)]
+        let mut command = match self.loader {
+            Some((loader, libraries)) => {
+                let mut command = Command::new(loader);
+                command.arg("--library-path").arg(libraries);
+                if let Some(arg0) = self.arg0 {
+                    command.arg("--argv0").arg(arg0);
+                }
+                command.arg(self.path);
+                command
+            }
+            None => {
+                let mut command = Command::new(self.path);
+                if let Some(arg0) = self.arg0 {
+                    command.arg0(arg0);
+                }
+                command
+            }
+        };
+        #[cfg(not(unix))]
+        let mut command = Command::new(self.path);
+        command.args(self.arguments);
+
+        if self.clear_environment {
+            command.env_clear();
+        }
+        for (key, value) in self.environment {
+            match value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key),
+            };
+        }
+        if !self.preload.is_empty() {
+            command.env("LD_PRELOAD", self.preload.join(OsStr::new(":")));
+        }
+        if let Some(directory) = self.current_dir {
+            command.current_dir(directory);
+        }
+
+        command
+            .stdin(process::Stdio::piped())
+            .stdout(process::Stdio::piped());
+        #[cfg(unix)]
+        let pty = match self.pty {
+            Some(settings) => {
+                let (master, slave) = super::pty::open(settings)?;
+                command.stdin(slave.try_clone()?).stdout(slave);
+                unsafe { command.pre_exec(super::pty::claim) };
+                Some(master)
+            }
+            None => None,
+        };
+        #[cfg(unix)]
+        let stderr = match (self.stderr, &pty) {
+            (Some(stderr), _) => stderr,
+            (None, Some(_)) => Stderr::Merge,
+            (None, None) => Stderr::default(),
+        };
+        #[cfg(not(unix))]
+        let stderr = self.stderr.unwrap_or_default();
+        stderr.apply(&mut command)?;
+
+        #[cfg(unix)]
+        {
+            if let Some(mask) = self.umask {
+                unsafe {
+                    command.pre_exec(move || {
+                        libc::umask(mask);
+                        Ok(())
+                    })
+                };
+            }
+            if !self.fds.is_empty() {
+                let mut fds: Vec<_> = self
+                    .fds
+                    .iter()
+                    .map(|(target, fd)| (*target, fd.as_raw_fd()))
+                    .collect();
+                unsafe { command.pre_exec(move || inherit(&mut fds)) };
+            }
+        }
+
+        Ok(Stdio {
+            child: command.spawn()?,
+            timing: Timing::of::<Stdio>(),
+            #[cfg(unix)]
+            pty,
+        })
+    }
+}
+
+/**
+Moves each file descriptor to its target number. Meant to be run in a freshly forked child.
+
+All of them are first duplicated above the highest target, so that moving one into place can't
+replace another that has yet to be moved (e.g. when two are swapped). The duplicates are closed on
+`exec`.
+*/
+#[cfg(unix)]
+fn inherit(fds: &mut [(RawFd, RawFd)]) -> io::Result<()> {
+    let above = fds.iter().map(|&(target, _)| target).max().unwrap_or(0) + 1;
+    for (_, fd) in fds.iter_mut() {
+        *fd = unsafe { libc::fcntl(*fd, libc::F_DUPFD_CLOEXEC, above) };
+        if *fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    for &(target, fd) in fds.iter() {
+        if unsafe { libc::dup2(fd, target) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/**
+Launch a [child process](tokio::process::Child) for interaction. See [`Builder`] for more options
+(e.g. [handling stderr](Builder::stderr)).
+*/
 pub async fn interact<I>(
     path: impl AsRef<Path>,
     arguments: Option<I>,
@@ -77,20 +479,37 @@ where
     I: IntoIterator,
     <I as IntoIterator>::Item: AsRef<OsStr>,
 {
-    let mut command = Command::new(path.as_ref());
-    command
-        .stderr(process::Stdio::inherit())
-        .stdin(process::Stdio::piped())
-        .stdout(process::Stdio::piped());
+    let mut builder = Builder::new(path.as_ref());
+    if let Some(arguments) = arguments {
+        builder = builder.args(arguments);
+    }
+    Ok(builder.spawn()?)
+}
+
+/**
+Like [`interact`], but the child process's stdin, stdout and stderr are connected to a new
+[pseudo-terminal](super::pty) (which also becomes its controlling terminal) instead of pipes.
+*/
+#[cfg(unix)]
+pub async fn interact_pty<I>(
+    path: impl AsRef<Path>,
+    arguments: Option<I>,
+    settings: Pty,
+) -> Result<Stdio, Box<dyn Error + Send + Sync>>
+where
+    I: IntoIterator,
+    <I as IntoIterator>::Item: AsRef<OsStr>,
+{
+    let mut builder = Builder::new(path.as_ref()).pty(settings);
     if let Some(arguments) = arguments {
-        command.args(arguments);
+        builder = builder.args(arguments);
     }
-    Ok(Stdio(command.spawn()?))
+    Ok(builder.spawn()?)
 }
 
 impl PID for Stdio {
     async fn get_pid(&self) -> Result<u32, Box<dyn Error + Send + Sync>> {
-        if let Some(pid) = self.0.id() {
+        if let Some(pid) = self.child.id() {
             Ok(pid)
         } else {
             Err(Box::new(io::Error::from(io::ErrorKind::NotFound)))
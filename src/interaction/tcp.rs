@@ -1,19 +1,150 @@
 #![cfg(feature = "tcp")]
 
-use super::Interaction;
-use std::time::Duration;
-use tokio::{io::AsyncWriteExt, net::TcpStream};
+use super::{Interaction, Timing};
+use std::{
+    error::Error,
+    io,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    time,
+};
 
-impl Interaction for TcpStream {
+#[derive(Debug)]
+pub struct Tcp {
+    stream: TcpStream,
+    timing: Timing,
+}
+impl Deref for Tcp {
+    type Target = TcpStream;
+
+    fn deref(&self) -> &Self::Target {
+        &self.stream
+    }
+}
+impl DerefMut for Tcp {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.stream
+    }
+}
+impl From<TcpStream> for Tcp {
+    fn from(stream: TcpStream) -> Self {
+        Self {
+            stream,
+            timing: Timing::of::<Tcp>(),
+        }
+    }
+}
+impl AsyncRead for Tcp {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+impl AsyncWrite for Tcp {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+impl Interaction for Tcp {
     const TIMEOUT: Duration = Duration::from_millis(50);
     const REPEAT: usize = 5;
 
-    async fn close(mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    async fn close(mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
         Ok(self.shutdown().await?)
     }
+
+    fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    fn timing_mut(&mut self) -> &mut Timing {
+        &mut self.timing
+    }
 }
 
 /// Open a TCP [interaction](Interaction) using [tokio](tokio::net::TcpStream).
-pub async fn interact(url: &'static str) -> std::io::Result<TcpStream> {
-    Ok(TcpStream::connect(url).await?)
+pub async fn interact(url: &'static str) -> io::Result<Tcp> {
+    Ok(TcpStream::connect(url).await?.into())
+}
+
+/**
+A TCP listener that accepts inbound connections as [interactions](Interaction), e.g. for catching
+reverse shells. When binding to port 0, the port chosen by the operating system can be retrieved
+with [`local_addr`](TcpListener::local_addr) before accepting.
+```no_run
+use libspl::{Interaction, interaction::tcp::Listener};
+
+# use std::error::Error;
+# #[tokio::main]
+# async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+let listener = Listener::bind("0.0.0.0:0").await?;
+let port = listener.local_addr()?.port();
+// Make the target connect back to `port`...
+let mut shell = listener.accept(None).await?;
+shell.send_line(b"id").await?;
+# Ok(())
+# }
+```
+*/
+#[derive(Debug)]
+pub struct Listener(TcpListener);
+impl Listener {
+    pub async fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self(TcpListener::bind(address).await?))
+    }
+
+    /**
+    Waits for an inbound connection. If `timeout` is provided and it elapses first, fails with
+    [`TimedOut`](io::ErrorKind::TimedOut).
+    */
+    pub async fn accept(&self, timeout: Option<Duration>) -> io::Result<Tcp> {
+        let accept = self.0.accept();
+        let (stream, _) = match timeout {
+            Some(duration) => time::timeout(duration, accept)
+                .await
+                .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()))?,
+            None => accept.await?,
+        };
+        Ok(stream.into())
+    }
+}
+impl Deref for Listener {
+    type Target = TcpListener;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/**
+Listen for a single inbound TCP connection and return it as an [interaction](Interaction). See
+[`Listener::accept`] for the meaning of `timeout`. The port is not known until a connection has
+been accepted, so to bind to port 0, use a [`Listener`] instead.
+*/
+pub async fn listen(address: impl ToSocketAddrs, timeout: Option<Duration>) -> io::Result<Tcp> {
+    Listener::bind(address).await?.accept(timeout).await
 }
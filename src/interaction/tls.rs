@@ -0,0 +1,280 @@
+#![cfg(feature = "tls")]
+
+use super::{Interaction, Timing, tcp::Tcp};
+use std::{
+    error::Error,
+    io,
+    ops::{Deref, DerefMut},
+    path::Path,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf},
+    net::TcpStream,
+};
+use tokio_rustls::{
+    TlsConnector,
+    client::TlsStream,
+    rustls::{
+        ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+        client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        crypto::{CryptoProvider, ring, verify_tls12_signature, verify_tls13_signature},
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime, pem::PemObject},
+    },
+};
+
+#[derive(Debug)]
+pub struct Tls {
+    stream: TlsStream<TcpStream>,
+    timing: Timing,
+}
+impl Deref for Tls {
+    type Target = TlsStream<TcpStream>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.stream
+    }
+}
+impl DerefMut for Tls {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.stream
+    }
+}
+impl From<TlsStream<TcpStream>> for Tls {
+    fn from(stream: TlsStream<TcpStream>) -> Self {
+        Self {
+            stream,
+            timing: Timing::of::<Tls>(),
+        }
+    }
+}
+impl AsyncRead for Tls {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+impl AsyncWrite for Tls {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+impl Interaction for Tls {
+    const TIMEOUT: Duration = Tcp::TIMEOUT;
+    const REPEAT: usize = Tcp::REPEAT;
+
+    async fn close(mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(self.shutdown().await?)
+    }
+
+    fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    fn timing_mut(&mut self) -> &mut Timing {
+        &mut self.timing
+    }
+}
+
+/**
+Configures and opens TLS [interactions](Interaction).
+```no_run
+use libspl::interaction::tls::Builder;
+
+# use std::error::Error;
+# #[tokio::main]
+# async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+let interaction = Builder::new()
+    .server_name("challenge.example.com")
+    .insecure()
+    .connect("10.0.0.1:1337")
+    .await?;
+# Ok(())
+# }
+```
+*/
+#[derive(Debug)]
+pub struct Builder {
+    roots: RootCertStore,
+    verify: bool,
+    server_name: Option<String>,
+    identity: Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)>,
+}
+impl Builder {
+    /// Trusts the [Mozilla root certificates](webpki_roots), and sends no client certificate.
+    pub fn new() -> Self {
+        Self {
+            roots: RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            },
+            verify: true,
+            server_name: None,
+            identity: None,
+        }
+    }
+
+    /**
+    Sets the name that is sent to the server (SNI) and checked against its certificate. By
+    default, this is the host part of the address passed to [`connect`](Builder::connect).
+    */
+    pub fn server_name(mut self, name: impl Into<String>) -> Self {
+        self.server_name = Some(name.into());
+        self
+    }
+
+    /// Accepts any certificate the server presents, without verifying it.
+    pub fn insecure(mut self) -> Self {
+        self.verify = false;
+        self
+    }
+
+    /// Additionally trusts the certificate(s) in the PEM file at `path`.
+    pub fn ca(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        for certificate in CertificateDer::pem_file_iter(path).map_err(invalid)? {
+            self.roots
+                .add(certificate.map_err(invalid)?)
+                .map_err(invalid)?;
+        }
+        Ok(self)
+    }
+
+    /**
+    Authenticates with the certificate chain in the PEM file at `certificate` and the private key
+    in the PEM file at `key`.
+    */
+    pub fn client_cert(
+        mut self,
+        certificate: impl AsRef<Path>,
+        key: impl AsRef<Path>,
+    ) -> io::Result<Self> {
+        self.identity = Some((
+            CertificateDer::pem_file_iter(certificate)
+                .map_err(invalid)?
+                .collect::<Result<_, _>>()
+                .map_err(invalid)?,
+            PrivateKeyDer::from_pem_file(key).map_err(invalid)?,
+        ));
+        Ok(self)
+    }
+
+    /// Connects to `address` (`host:port`) and performs the TLS handshake.
+    pub async fn connect(self, address: &str) -> io::Result<Tls> {
+        let provider = Arc::new(ring::default_provider());
+        let config = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(io::Error::other)?;
+        let config = if self.verify {
+            config.with_root_certificates(self.roots)
+        } else {
+            config
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(NoVerification(provider)))
+        };
+        let config = match self.identity {
+            Some((chain, key)) => config.with_client_auth_cert(chain, key).map_err(invalid)?,
+            None => config.with_no_client_auth(),
+        };
+
+        let server_name = match self.server_name {
+            Some(name) => name,
+            None => host(address).to_owned(),
+        };
+        let server_name = ServerName::try_from(server_name).map_err(invalid)?;
+        Ok(TlsConnector::from(Arc::new(config))
+            .connect(server_name, TcpStream::connect(address).await?)
+            .await?
+            .into())
+    }
+}
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Accepts every server certificate. See [`Builder::insecure`].
+#[derive(Debug)]
+struct NoVerification(Arc<CryptoProvider>);
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _: &CertificateDer<'_>,
+        _: &[CertificateDer<'_>],
+        _: &ServerName<'_>,
+        _: &[u8],
+        _: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        verify_tls12_signature(
+            message,
+            certificate,
+            signature,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        verify_tls13_signature(
+            message,
+            certificate,
+            signature,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn invalid(error: impl Into<Box<dyn Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// The host part of `address`, without the port or IPv6 brackets.
+fn host(address: &str) -> &str {
+    address
+        .rsplit_once(':')
+        .map_or(address, |(host, _)| host)
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+}
+
+/// Open a TLS [interaction](Interaction) with the default [`Builder`] settings.
+pub async fn interact(address: &'static str) -> io::Result<Tls> {
+    Builder::new().connect(address).await
+}
@@ -0,0 +1,501 @@
+/*!
+Transcripts of everything exchanged with an [`Interaction`], and replaying them.
+
+A transcript is a file of JSON lines, each describing one [`Record`]:
+```text
+{"direction":"received","time":1760700000.123456,"data":"57656c636f6d65210a"}
+{"direction":"sent","time":1760700000.180012,"data":"310a"}
+```
+`time` is the number of seconds since the UNIX epoch (with microsecond precision) at which the data
+passed through the [`Recorder`], and `data` is the data itself, hex-encoded.
+
+A transcript can be played back with [`Replay`], e.g. to test an exploit offline against a
+conversation with a remote service that is no longer available.
+*/
+
+use super::{Direction, Interaction, PID, Timing, buffered::poll_read_buffered};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+    fs::File,
+    future::Future,
+    io::{self, Write},
+    path::Path,
+    pin::Pin,
+    str::FromStr,
+    task::{Context, Poll, Waker, ready},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    sync::{
+        mpsc::{UnboundedSender, unbounded_channel},
+        oneshot,
+    },
+    time::{Instant, Sleep, sleep_until},
+};
+
+/// One transmission through an [`Interaction`]. See the [module documentation](self).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    pub direction: Direction,
+    pub time: SystemTime,
+    pub data: Vec<u8>,
+}
+impl Display for Record {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let time = self.time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        write!(
+            f,
+            r#"{{"direction":"{}","time":{}.{:06},"data":""#,
+            match self.direction {
+                Direction::Received => "received",
+                Direction::Sent => "sent",
+            },
+            time.as_secs(),
+            time.subsec_micros()
+        )?;
+        for byte in &self.data {
+            write!(f, "{byte:02x}")?;
+        }
+        f.write_str(r#""}"#)
+    }
+}
+impl FromStr for Record {
+    type Err = io::Error;
+
+    /// Parses a single line of a transcript. Unknown fields are ignored.
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let fields = line
+            .trim()
+            .strip_prefix('{')
+            .and_then(|line| line.strip_suffix('}'))
+            .ok_or_else(|| invalid(format!("not a JSON object: `{line}`")))?;
+
+        let (mut direction, mut time, mut data) = (None, None, None);
+        for field in fields.split(',') {
+            let (key, value) = field
+                .split_once(':')
+                .ok_or_else(|| invalid(format!("invalid field `{field}`")))?;
+            let string = || value.trim().trim_matches('"');
+            match key.trim().trim_matches('"') {
+                "direction" => {
+                    direction = Some(match string() {
+                        "received" => Direction::Received,
+                        "sent" => Direction::Sent,
+                        other => return Err(invalid(format!("invalid direction `{other}`"))),
+                    })
+                }
+                "time" => {
+                    time = Some(
+                        value
+                            .trim()
+                            .parse::<f64>()
+                            .ok()
+                            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                            .map(|time| UNIX_EPOCH + time)
+                            .ok_or_else(|| invalid(format!("invalid time `{}`", value.trim())))?,
+                    )
+                }
+                "data" => {
+                    let hex = string();
+                    data = Some(
+                        (0..hex.len())
+                            .step_by(2)
+                            .map(|i| {
+                                hex.get(i..i + 2)
+                                    .and_then(|b| u8::from_str_radix(b, 16).ok())
+                            })
+                            .collect::<Option<Vec<u8>>>()
+                            .ok_or_else(|| invalid(format!("invalid data `{hex}`")))?,
+                    )
+                }
+                _ => {}
+            }
+        }
+        match (direction, time, data) {
+            (Some(direction), Some(time), Some(data)) => Ok(Self {
+                direction,
+                time,
+                data,
+            }),
+            _ => Err(invalid(format!("missing field in `{line}`"))),
+        }
+    }
+}
+
+/**
+Reads all [`Record`]s from the transcript at `path`, skipping blank lines.
+*/
+pub fn load(path: impl AsRef<Path>) -> io::Result<Vec<Record>> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/**
+An [`Interaction`] that writes a [`Record`] of every transmission through the wrapped
+[`Interaction`] to a transcript, without otherwise changing it.
+```no_run
+use libspl::{interact, Interaction, interaction::transcript::Recorder};
+
+# use std::error::Error;
+# #[tokio::main]
+# async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+let mut interaction = Recorder::create(interact!(tcp, "www.example.com:65535").await?, "run.jsonl")?;
+interaction.send_line_after(b"> ", b"1").await?;
+interaction.close().await?;
+# Ok(())
+# }
+```
+
+Records are written (and flushed) by a separate thread as soon as the data passes through, so the
+async runtime is never blocked on the transcript. Data is read from the wrapped [`Interaction`] in
+large reads (and handed out from an internal buffer), so each [`Received`](Direction::Received)
+record holds everything that was available at once rather than a single byte. If writing a record
+fails, recording stops (the wrapped [`Interaction`] is unaffected). [`close`](Interaction::close)
+waits until every record has been written, and returns the error if there was one.
+*/
+pub struct Recorder<I> {
+    inner: I,
+    /// Hands records to the thread that writes them to the transcript.
+    records: UnboundedSender<Record>,
+    /// Receives the outcome of writing the transcript once the thread is done.
+    written: oneshot::Receiver<io::Result<()>>,
+    buffer: VecDeque<u8>,
+}
+impl<I> Recorder<I> {
+    /// Records to `transcript`.
+    pub fn new(inner: I, mut transcript: impl Write + Send + 'static) -> Self {
+        let (records, mut receiver) = unbounded_channel::<Record>();
+        let (result, written) = oneshot::channel();
+        thread::spawn(move || {
+            let _ = result.send((|| {
+                while let Some(record) = receiver.blocking_recv() {
+                    writeln!(transcript, "{record}")?;
+                    transcript.flush()?;
+                }
+                Ok(())
+            })());
+        });
+        Self {
+            inner,
+            records,
+            written,
+            buffer: VecDeque::new(),
+        }
+    }
+
+    /// Records to a new file at `path`, truncating it if it already exists.
+    pub fn create(inner: I, path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(inner, File::create(path)?))
+    }
+
+    pub fn get_ref(&self) -> &I {
+        &self.inner
+    }
+
+    /**
+    Accesses the wrapped [`Interaction`] directly. Nothing done through it is recorded, and reading
+    from it will bypass any data that is still in the buffer.
+    */
+    pub fn get_mut(&mut self) -> &mut I {
+        &mut self.inner
+    }
+
+    /// Unwraps the [`Interaction`], returning it along with any data still in the buffer.
+    pub fn into_inner(self) -> (I, Vec<u8>) {
+        (self.inner, self.buffer.into())
+    }
+}
+/// Hands a [`Record`] of `data` to the thread writing the transcript of a [`Recorder`].
+fn record(records: &UnboundedSender<Record>, direction: Direction, data: &[u8]) {
+    if data.is_empty() {
+        return;
+    }
+    // Fails only once recording has stopped, in which case the error is returned by `close`.
+    let _ = records.send(Record {
+        direction,
+        time: SystemTime::now(),
+        data: data.to_vec(),
+    });
+}
+impl<I: Debug> Debug for Recorder<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+impl<I: AsyncRead + Unpin> AsyncRead for Recorder<I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        poll_read_buffered(&mut this.inner, &mut this.buffer, cx, buf, |data| {
+            record(&this.records, Direction::Received, data)
+        })
+    }
+}
+impl<I: AsyncWrite + Unpin> AsyncWrite for Recorder<I> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = self.get_mut();
+        let n = ready!(Pin::new(&mut this.inner).poll_write(cx, buf))?;
+        record(&this.records, Direction::Sent, &buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+impl<I: Interaction + Send> Interaction for Recorder<I> {
+    const TIMEOUT: Duration = I::TIMEOUT;
+    const REPEAT: usize = I::REPEAT;
+
+    async fn close(self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let Self {
+            inner,
+            records,
+            written,
+            ..
+        } = self;
+        // Lets the thread finish once it has written everything that was recorded.
+        drop(records);
+        inner.close().await?;
+        Ok(written.await??)
+    }
+
+    fn timing(&self) -> &Timing {
+        self.inner.timing()
+    }
+
+    fn timing_mut(&mut self) -> &mut Timing {
+        self.inner.timing_mut()
+    }
+}
+
+impl<I: PID> PID for Recorder<I> {
+    async fn get_pid(&self) -> Result<u32, Box<dyn Error + Send + Sync>> {
+        self.inner.get_pid().await
+    }
+}
+
+/**
+An [`Interaction`] that plays the part of the remote stream in a recorded transcript (see
+[`Recorder`]).
+
+[`Received`](Direction::Received) records are handed out with their original timing, relative to
+the record before them, so [`read_chunk`](Interaction::read_chunk) splits them into the same chunks
+as the real transport would have (provided the [`Timing`] matches). Received data that was recorded
+after a [`Sent`](Direction::Sent) record is held back until that data has been written.
+
+Everything written must match the [`Sent`](Direction::Sent) records, in order (though not
+necessarily in the same number of writes); a mismatching write fails with
+[`InvalidData`](io::ErrorKind::InvalidData).
+```no_run
+use libspl::{interact, Interaction};
+use std::time::Duration;
+
+# use std::error::Error;
+# #[tokio::main]
+# async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+let mut interaction = interact!(transcript, "run.jsonl"; timeout = Duration::from_millis(50)).await?;
+interaction.send_line_after(b"> ", b"1").await?;
+interaction.close().await?;
+# Ok(())
+# }
+```
+*/
+pub struct Replay {
+    records: VecDeque<Record>,
+    timing: Timing,
+    /// The time of the last record that was consumed, and when that happened.
+    last: Option<(SystemTime, Instant)>,
+    delay: Option<Pin<Box<Sleep>>>,
+    /// How much of the next [`Sent`](Direction::Sent) record has already been written.
+    written: usize,
+    waker: Option<Waker>,
+}
+impl Replay {
+    pub fn new(records: impl IntoIterator<Item = Record>, timing: Timing) -> Self {
+        Self {
+            records: records
+                .into_iter()
+                .filter(|record| !record.data.is_empty())
+                .collect(),
+            timing,
+            last: None,
+            delay: None,
+            written: 0,
+            waker: None,
+        }
+    }
+
+    /// Replays the transcript at `path`.
+    pub fn load(path: impl AsRef<Path>, timing: Timing) -> io::Result<Self> {
+        Ok(Self::new(load(path)?, timing))
+    }
+
+    /// The records that have not been replayed yet.
+    pub fn remaining(&self) -> &VecDeque<Record> {
+        &self.records
+    }
+
+    /// Notes that (the rest of) the record from `time` has just been consumed.
+    fn consume(&mut self, time: SystemTime) {
+        let time = match self.last {
+            Some((last, _)) => time.max(last),
+            None => time,
+        };
+        self.last = Some((time, Instant::now()));
+    }
+}
+impl Debug for Replay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Replay")
+            .field("records", &self.records)
+            .field("timing", &self.timing)
+            .finish_non_exhaustive()
+    }
+}
+impl AsyncRead for Replay {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let Some(record) = this.records.front() else {
+            return Poll::Ready(Ok(()));
+        };
+        if record.direction == Direction::Sent {
+            this.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        let time = record.time;
+        let delay = this.delay.get_or_insert_with(|| {
+            Box::pin(sleep_until(match this.last {
+                Some((last, at)) => at + time.duration_since(last).unwrap_or_default(),
+                None => Instant::now(),
+            }))
+        });
+        ready!(delay.as_mut().poll(cx));
+        this.delay = None;
+
+        let record = this.records.front_mut().expect("record");
+        let n = buf.remaining().min(record.data.len());
+        buf.put_slice(&record.data[..n]);
+        record.data.drain(..n);
+        if record.data.is_empty() {
+            this.records.pop_front();
+        }
+        this.consume(time);
+        Poll::Ready(Ok(()))
+    }
+}
+impl AsyncWrite for Replay {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = self.get_mut();
+        let Some(index) = this
+            .records
+            .iter()
+            .position(|record| record.direction == Direction::Sent)
+        else {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unexpected write `{}`", buf.escape_ascii()),
+            )));
+        };
+
+        let expected = &this.records[index].data[this.written..];
+        let n = buf.len().min(expected.len());
+        if buf[..n] != expected[..n] {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "expected write `{}`, got `{}`",
+                    expected.escape_ascii(),
+                    buf.escape_ascii()
+                ),
+            )));
+        }
+
+        this.written += n;
+        if this.written == this.records[index].data.len() {
+            let time = this.records.remove(index).expect("record").time;
+            this.written = 0;
+            this.consume(time);
+            if let Some(waker) = this.waker.take() {
+                waker.wake();
+            }
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+impl Interaction for Replay {
+    const TIMEOUT: Duration = Duration::from_millis(50);
+
+    /// Fails if any [`Sent`](Direction::Sent) records were never written.
+    async fn close(self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self
+            .records
+            .iter()
+            .find(|record| record.direction == Direction::Sent)
+        {
+            Some(record) => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "closed before writing `{}`",
+                    record.data[self.written..].escape_ascii()
+                ),
+            )
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    fn timing_mut(&mut self) -> &mut Timing {
+        &mut self.timing
+    }
+}
+
+/// Replay the transcript at `path` (see [`Replay`]).
+pub async fn interact(path: impl AsRef<Path>) -> io::Result<Replay> {
+    Replay::load(path, Timing::of::<Replay>())
+}
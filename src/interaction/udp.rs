@@ -0,0 +1,139 @@
+#![cfg(feature = "udp")]
+
+use super::{Interaction, Timing, buffered::drain_into};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    ops::Deref,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{ToSocketAddrs, UdpSocket, lookup_host},
+};
+
+/// The largest possible UDP payload.
+const MAX_DATAGRAM: usize = 65535;
+
+/**
+A connected UDP socket as an [`Interaction`].
+
+Every write is sent as a single datagram. Reads hand out the payloads of incoming datagrams in
+order, so a [chunk](Interaction::read_chunk_bytes) is made up of all the datagrams that arrive
+without a gap of more than the [`Timing`]'s timeout. Use [`recv_datagram`](Udp::recv_datagram) to
+read exactly one datagram at a time instead.
+*/
+#[derive(Debug)]
+pub struct Udp {
+    socket: UdpSocket,
+    timing: Timing,
+    /// The unread rest of a datagram that didn't fit into the reader's buffer.
+    buffer: VecDeque<u8>,
+    /// Where datagrams are received, reused so that each read doesn't have to allocate it.
+    datagram: Box<[u8]>,
+}
+impl Udp {
+    /**
+    Receives the next datagram, or the rest of the current one if a read has only consumed part
+    of it.
+    */
+    pub async fn recv_datagram(&mut self) -> io::Result<Vec<u8>> {
+        if !self.buffer.is_empty() {
+            return Ok(self.buffer.drain(..).collect());
+        }
+        let n = self.socket.recv(&mut self.datagram).await?;
+        Ok(self.datagram[..n].to_vec())
+    }
+}
+impl Deref for Udp {
+    type Target = UdpSocket;
+
+    fn deref(&self) -> &Self::Target {
+        &self.socket
+    }
+}
+impl From<UdpSocket> for Udp {
+    /// `socket` must already be [connected](UdpSocket::connect).
+    fn from(socket: UdpSocket) -> Self {
+        Self {
+            socket,
+            timing: Timing::of::<Udp>(),
+            buffer: VecDeque::new(),
+            datagram: vec![0; MAX_DATAGRAM].into_boxed_slice(),
+        }
+    }
+}
+impl AsyncRead for Udp {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        /*
+        Empty datagrams are skipped, since handing one out as an empty read would signal the end of
+        the stream.
+        */
+        while this.buffer.is_empty() {
+            let mut datagram = ReadBuf::new(&mut this.datagram);
+            ready!(this.socket.poll_recv(cx, &mut datagram))?;
+            this.buffer.extend(datagram.filled());
+        }
+        drain_into(&mut this.buffer, buf);
+        Poll::Ready(Ok(()))
+    }
+}
+impl AsyncWrite for Udp {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        self.socket.poll_send(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+impl Interaction for Udp {
+    const TIMEOUT: Duration = Duration::from_millis(50);
+    const REPEAT: usize = 5;
+
+    async fn close(self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+
+    fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    fn timing_mut(&mut self) -> &mut Timing {
+        &mut self.timing
+    }
+}
+
+/**
+Open a UDP [interaction](Interaction) with `address`, from an ephemeral local port.
+*/
+pub async fn interact(address: impl ToSocketAddrs) -> io::Result<Udp> {
+    let address = lookup_host(address)
+        .await?
+        .next()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::AddrNotAvailable))?;
+    let socket = UdpSocket::bind(match address {
+        SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+    })
+    .await?;
+    socket.connect(address).await?;
+    Ok(socket.into())
+}
@@ -0,0 +1,285 @@
+#![cfg(feature = "unix")]
+
+use super::{Interaction, PID, Timing};
+use std::{
+    error::Error,
+    io,
+    ops::{Deref, DerefMut},
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf},
+    net::{
+        UnixListener, UnixStream,
+        unix::pipe::{self, Receiver, Sender},
+    },
+    time,
+};
+
+/**
+A Unix domain socket connection as an [`Interaction`].
+
+Paths starting with `@` refer to sockets in the abstract namespace (Linux only), as shown by
+`ss -x`.
+*/
+#[derive(Debug)]
+pub struct Unix {
+    stream: UnixStream,
+    timing: Timing,
+}
+impl Deref for Unix {
+    type Target = UnixStream;
+
+    fn deref(&self) -> &Self::Target {
+        &self.stream
+    }
+}
+impl DerefMut for Unix {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.stream
+    }
+}
+impl From<UnixStream> for Unix {
+    fn from(stream: UnixStream) -> Self {
+        Self {
+            stream,
+            timing: Timing::of::<Unix>(),
+        }
+    }
+}
+impl AsyncRead for Unix {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+impl AsyncWrite for Unix {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+impl Interaction for Unix {
+    const TIMEOUT: Duration = Duration::from_millis(20);
+    const REPEAT: usize = 3;
+
+    async fn close(mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(self.shutdown().await?)
+    }
+
+    fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    fn timing_mut(&mut self) -> &mut Timing {
+        &mut self.timing
+    }
+}
+
+/// Retrieves the PID of the peer process from the socket's credentials.
+impl PID for Unix {
+    async fn get_pid(&self) -> Result<u32, Box<dyn Error + Send + Sync>> {
+        Ok(self
+            .peer_cred()?
+            .pid()
+            .ok_or("peer credentials do not include a PID")? as u32)
+    }
+}
+
+/// A Unix domain socket listener that accepts connections as [interactions](Interaction).
+#[derive(Debug)]
+pub struct Listener(UnixListener);
+impl Listener {
+    /// Binds to `path` (see [`Unix`] for abstract socket names).
+    pub fn bind(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        Ok(Self(match abstract_name(path) {
+            Some(name) => UnixListener::from_std(bind_abstract(name)?)?,
+            None => UnixListener::bind(path)?,
+        }))
+    }
+
+    /**
+    Waits for an inbound connection. If `timeout` is provided and it elapses first, fails with
+    [`TimedOut`](io::ErrorKind::TimedOut).
+    */
+    pub async fn accept(&self, timeout: Option<Duration>) -> io::Result<Unix> {
+        let accept = self.0.accept();
+        let (stream, _) = match timeout {
+            Some(duration) => time::timeout(duration, accept)
+                .await
+                .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()))?,
+            None => accept.await?,
+        };
+        Ok(stream.into())
+    }
+}
+impl Deref for Listener {
+    type Target = UnixListener;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/**
+A pair of named pipes as an [`Interaction`]: data is read from one FIFO and written to the other.
+*/
+#[derive(Debug)]
+pub struct Fifo {
+    input: Receiver,
+    output: Sender,
+    timing: Timing,
+}
+impl Fifo {
+    /**
+    Opens the FIFOs at `input` (to read from) and `output` (to write to), waiting until another
+    process has `output` open for reading. `input` is opened first, so the other process may open
+    its ends in either order. Reads reach the end of the stream once every process that opened
+    `input` for writing has closed it.
+    */
+    pub async fn open(input: impl AsRef<Path>, output: impl AsRef<Path>) -> io::Result<Self> {
+        let input = pipe::OpenOptions::new().open_receiver(input)?;
+        let output = output.as_ref();
+        let output = loop {
+            match pipe::OpenOptions::new().open_sender(output) {
+                // There is no reader yet.
+                Err(error) if error.raw_os_error() == Some(libc::ENXIO) => {
+                    time::sleep(Self::TIMEOUT).await
+                }
+                result => break result?,
+            }
+        };
+        Ok(Self {
+            input,
+            output,
+            timing: Timing::of::<Fifo>(),
+        })
+    }
+
+    pub fn input(&self) -> &Receiver {
+        &self.input
+    }
+
+    pub fn output(&self) -> &Sender {
+        &self.output
+    }
+}
+impl AsyncRead for Fifo {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.input).poll_read(cx, buf)
+    }
+}
+impl AsyncWrite for Fifo {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        Pin::new(&mut self.output).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.output).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), io::Error>> {
+        Pin::new(&mut self.output).poll_shutdown(cx)
+    }
+}
+impl Interaction for Fifo {
+    const TIMEOUT: Duration = Duration::from_millis(20);
+    const REPEAT: usize = 3;
+
+    async fn close(self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+
+    fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    fn timing_mut(&mut self) -> &mut Timing {
+        &mut self.timing
+    }
+}
+
+/// The name of the abstract socket `path` refers to, if any (see [`Unix`]).
+fn abstract_name(path: &Path) -> Option<&[u8]> {
+    path.as_os_str().as_encoded_bytes().strip_prefix(b"@")
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(any(target_os = "linux", target_os = "android"))] {
+        #[cfg(target_os = "android")]
+        use std::os::android::net::SocketAddrExt;
+        #[cfg(target_os = "linux")]
+        use std::os::linux::net::SocketAddrExt;
+        use std::os::unix::net::{self, SocketAddr};
+
+        fn connect_abstract(name: &[u8]) -> io::Result<net::UnixStream> {
+            let stream = net::UnixStream::connect_addr(&SocketAddr::from_abstract_name(name)?)?;
+            stream.set_nonblocking(true)?;
+            Ok(stream)
+        }
+
+        fn bind_abstract(name: &[u8]) -> io::Result<net::UnixListener> {
+            let listener = net::UnixListener::bind_addr(&SocketAddr::from_abstract_name(name)?)?;
+            listener.set_nonblocking(true)?;
+            Ok(listener)
+        }
+    } else {
+        fn connect_abstract(_: &[u8]) -> io::Result<std::os::unix::net::UnixStream> {
+            Err(io::ErrorKind::Unsupported.into())
+        }
+
+        fn bind_abstract(_: &[u8]) -> io::Result<std::os::unix::net::UnixListener> {
+            Err(io::ErrorKind::Unsupported.into())
+        }
+    }
+}
+
+/// Open a Unix domain socket [interaction](Interaction) (see [`Unix`]).
+pub async fn interact(path: impl AsRef<Path>) -> io::Result<Unix> {
+    let path = path.as_ref();
+    Ok(match abstract_name(path) {
+        Some(name) => UnixStream::from_std(connect_abstract(name)?)?,
+        None => UnixStream::connect(path).await?,
+    }
+    .into())
+}
+
+/**
+Listen for a single inbound connection on the Unix domain socket at `path` and return it as an
+[interaction](Interaction). See [`Listener::accept`] for the meaning of `timeout`.
+*/
+pub async fn listen(path: impl AsRef<Path>, timeout: Option<Duration>) -> io::Result<Unix> {
+    Listener::bind(path)?.accept(timeout).await
+}
@@ -83,11 +83,10 @@ where
     */
     async fn pad_both_with<const L: usize, const R: usize>(self, with: u8) -> [u8; R] {
         async move {
-            return self
-                .pad_with::<L>(Side::Left, with)
+            self.pad_with::<L>(Side::Left, with)
                 .await
                 .pad_with::<R>(Side::Right, with)
-                .await;
+                .await
         }
     }
 
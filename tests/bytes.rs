@@ -4,7 +4,7 @@ use libspl::{HexToBytes, Pad, Repeat, Side};
 use num_traits::PrimInt;
 use std::{fmt::Debug, num::IntErrorKind, str::FromStr};
 
-#[derive(Debug, World)]
+#[derive(Debug, Default, World)]
 struct BytesWorld {
     bytes: Vec<u8>,
     hex: Option<u32>,
@@ -13,15 +13,6 @@ struct BytesWorld {
 impl BytesWorld {
     const FINAL: usize = 32;
 }
-impl Default for BytesWorld {
-    fn default() -> Self {
-        Self {
-            bytes: Vec::new(),
-            hex: None,
-            integer: None,
-        }
-    }
-}
 
 #[derive(Debug, Default, Deref, DerefMut, Parameter)]
 #[param(name = "hex", regex = "0x([0-9A-Fa-f]+)")]
@@ -63,7 +54,7 @@ async fn when_i_pad_the_byte_string(
     side: String,
     with: String,
 ) {
-    assert!(with.bytes().count() <= 1);
+    assert!(with.len() <= 1);
     let (clone, side) = (
         bytes.clone(),
         match side.as_str() {
@@ -73,7 +64,7 @@ async fn when_i_pad_the_byte_string(
             string => unreachable!("expected '', 'left' or 'right', got '{}'", string),
         },
     );
-    *bytes = match (side, with.as_bytes().get(0)) {
+    *bytes = match (side, with.as_bytes().first()) {
         (Some(side), Some(&byte)) => clone.pad_with::<{ BytesWorld::FINAL }>(side, byte).await,
         (Some(side), None) => clone.pad(side).await,
         (None, Some(&byte)) => {
@@ -128,7 +119,7 @@ fn then_the_sequence_of_the_byte_string_will_be(
                             if trimmed.is_empty() {
                                 1
                             } else {
-                                match usize::from_str_radix(trimmed, 10) {
+                                match trimmed.parse::<usize>() {
                                     Ok(ok) => ok,
                                     Err(error) if matches!(error.kind(), IntErrorKind::Empty) => {
                                         todo!()
@@ -161,7 +152,7 @@ fn i_should_have_the_bytes(BytesWorld { bytes, .. }: &mut BytesWorld, step: &Ste
         } else if cell.starts_with("0x") {
             u8::from_str_radix(cell.trim_start_matches("0x"), 16)
         } else {
-            u8::from_str_radix(cell, 10)
+            cell.parse::<u8>()
         }
         .unwrap();
         assert_eq!(expected, actual);
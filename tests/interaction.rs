@@ -1,21 +1,36 @@
 use cucumber::{World, gherkin::Step, given, then, when};
-use libspl::Interaction;
+use libspl::{
+    Interaction,
+    interaction::{
+        Action, Buffered, Expected, Incomplete, Regex, RegexBuilder, Timing,
+        echo::{Echo, Format},
+        script::{Captures, Script},
+        transcript::{Record, Recorder, Replay},
+    },
+};
 use std::{
     collections::VecDeque,
     error::Error,
-    io::{self},
+    future::Future,
+    io::{self, Write},
     pin::Pin,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
     time::Duration,
 };
 use tokio::{
-    io::{AsyncRead, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf, duplex},
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf, duplex},
     join,
     time::sleep,
 };
 
 #[derive(Debug)]
-struct TestInteraction(DuplexStream);
+struct TestInteraction(DuplexStream, Timing);
+impl TestInteraction {
+    fn new(stream: DuplexStream) -> Self {
+        Self(stream, Timing::of::<Self>())
+    }
+}
 impl AsyncRead for TestInteraction {
     fn poll_read(
         mut self: Pin<&mut Self>,
@@ -49,40 +64,92 @@ impl Interaction for TestInteraction {
         self.shutdown().await?;
         Ok(())
     }
+
+    fn timing(&self) -> &Timing {
+        &self.1
+    }
+
+    fn timing_mut(&mut self) -> &mut Timing {
+        &mut self.1
+    }
+}
+
+#[derive(Debug)]
+struct SharedLog(Arc<Mutex<Vec<u8>>>);
+impl Write for SharedLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Write::write(&mut *self.0.lock().unwrap(), buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 #[derive(Debug, World)]
 struct InteractionWorld {
-    buffer: VecDeque<(String, Option<Duration>)>,
-    chunk: Option<String>,
+    buffer: VecDeque<(Vec<u8>, Option<Duration>)>,
+    buffered: Option<Buffered<TestInteraction>>,
+    captures: Option<Captures>,
+    chunk: Option<Vec<u8>>,
     duplex: DuplexStream,
+    error: Option<io::ErrorKind>,
+    expected: Option<Expected>,
     interaction: TestInteraction,
+    log: Arc<Mutex<Vec<u8>>>,
+    recorder: Option<Recorder<TestInteraction>>,
+    replay: Option<Replay>,
 }
 impl Default for InteractionWorld {
     fn default() -> Self {
         let (a, b) = duplex(2048);
         Self {
             buffer: Default::default(),
+            buffered: Default::default(),
+            captures: Default::default(),
             chunk: Default::default(),
             duplex: a,
-            interaction: TestInteraction(b),
+            error: Default::default(),
+            expected: Default::default(),
+            interaction: TestInteraction::new(b),
+            log: Default::default(),
+            recorder: Default::default(),
+            replay: Default::default(),
         }
     }
 }
 
-#[given(expr = "an Interaction with a {int} millisecond timeout")]
+#[given(regex = r"^an? (buffered |recorded )?Interaction with a (\d+) millisecond timeout$")]
 fn given_an_interaction_with_a_millisecond_timeout(
-    InteractionWorld { buffer, .. }: &mut InteractionWorld,
+    InteractionWorld {
+        buffer,
+        buffered,
+        duplex: remote,
+        log,
+        recorder,
+        ..
+    }: &mut InteractionWorld,
+    wrap: String,
     millis: u128,
     step: &Step,
 ) {
     assert_eq!(millis, TestInteraction::TIMEOUT.as_millis());
+    if !wrap.is_empty() {
+        let (a, b) = duplex(2048);
+        *remote = a;
+        let interaction = TestInteraction::new(b);
+        if wrap == "buffered " {
+            *buffered = Some(Buffered::new(interaction));
+        } else {
+            *recorder = Some(Recorder::new(interaction, SharedLog(log.clone())));
+        }
+    }
     if let Some(table) = &step.table {
         assert_eq!(2, table.row_width());
         for row in &table.rows {
             if let [string, wait] = &row[..] {
                 buffer.push_back((
-                    string.clone(),
+                    string.clone().into_bytes(),
                     if !wait.is_empty() {
                         Some(TestInteraction::TIMEOUT.mul_f64(wait.parse::<f64>().expect("f64")))
                     } else {
@@ -96,7 +163,64 @@ fn given_an_interaction_with_a_millisecond_timeout(
     }
 }
 
-#[when(regex = "I read (a|the last) chunk")]
+#[given(expr = "the remote sends the hex bytes {string}")]
+fn given_the_remote_sends_the_hex_bytes(
+    InteractionWorld { buffer, .. }: &mut InteractionWorld,
+    hex: String,
+) {
+    buffer.push_back((hex_to_bytes(&hex), None))
+}
+
+#[given(expr = "the remote sends {int} bytes of {string}")]
+fn given_the_remote_sends_bytes_of(
+    InteractionWorld { buffer, .. }: &mut InteractionWorld,
+    n: usize,
+    byte: String,
+) {
+    buffer.push_back((byte.repeat(n).into_bytes(), None))
+}
+
+fn hex_to_bytes(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("hex"))
+        .collect()
+}
+
+/// Writes the contents of `buffer` to `duplex` while `read` is running.
+async fn feed<T>(
+    buffer: &mut VecDeque<(Vec<u8>, Option<Duration>)>,
+    duplex: &mut DuplexStream,
+    read: impl Future<Output = T>,
+) -> T {
+    join!(
+        async {
+            while let Some((bytes, wait)) = buffer.pop_front() {
+                duplex.write_all(&bytes).await.expect("write");
+                if let Some(wait) = wait {
+                    sleep(wait).await;
+                }
+            }
+        },
+        read,
+    )
+    .1
+}
+
+#[given(expr = "the transcript")]
+fn given_the_transcript(InteractionWorld { replay, .. }: &mut InteractionWorld, step: &Step) {
+    *replay = Some(Replay::new(
+        step.docstring
+            .as_ref()
+            .expect("docstring")
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.parse().expect("record")),
+        Timing::of::<TestInteraction>(),
+    ));
+}
+
+#[when(regex = "^I read (a|the last) chunk( of bytes)?$")]
 async fn when_i_read_chunk(
     InteractionWorld {
         buffer,
@@ -106,32 +230,518 @@ async fn when_i_read_chunk(
         ..
     }: &mut InteractionWorld,
     function: String,
+    bytes: String,
 ) {
-    join!(
-        async {
-            while let Some((string, wait)) = buffer.pop_front() {
-                duplex.write(string.as_bytes()).await.expect("write");
-                if let Some(wait) = wait {
-                    sleep(wait).await;
+    *chunk = Some(
+        feed(buffer, duplex, async {
+            match (function.as_str(), bytes.is_empty()) {
+                ("a", true) => interaction.read_chunk().await.expect("chunk").into_bytes(),
+                ("a", false) => interaction.read_chunk_bytes().await.expect("chunk"),
+                ("the last", true) => interaction.read_last_chunk().await.into_bytes(),
+                ("the last", false) => interaction.read_last_chunk_bytes().await,
+                _ => unreachable!(),
+            }
+        })
+        .await,
+    );
+}
+
+#[when(regex = r"^I (set|temporarily set) the timeout to (\d+) timeouts$")]
+async fn when_i_set_the_timeout(
+    InteractionWorld {
+        buffer,
+        chunk,
+        duplex,
+        interaction,
+        ..
+    }: &mut InteractionWorld,
+    scope: String,
+    n: u32,
+) {
+    let timeout = TestInteraction::TIMEOUT * n;
+    if scope == "set" {
+        interaction.set_timeout(timeout);
+    } else {
+        let mut scoped = interaction.with_timeout(timeout);
+        assert_eq!(timeout, scoped.timing().timeout);
+        *chunk = Some(
+            feed(buffer, duplex, scoped.read_chunk_bytes())
+                .await
+                .expect("chunk"),
+        );
+    }
+}
+
+#[when(regex = r#"^I send (?:the line )?"([^"]*)"(?: after "([^"]*)")?$"#)]
+async fn when_i_send(
+    InteractionWorld {
+        buffer,
+        chunk,
+        duplex,
+        interaction,
+        ..
+    }: &mut InteractionWorld,
+    data: String,
+    prompt: String,
+    step: &Step,
+) {
+    let line = step.value.contains("the line");
+    let data = data.as_bytes();
+    let prompt = prompt.as_bytes();
+    let before = feed(buffer, duplex, async {
+        match (line, prompt.is_empty()) {
+            (true, true) => interaction.send_line(data).await.map(|_| Vec::new()),
+            (true, false) => interaction.send_line_after(prompt, data).await,
+            (false, _) => interaction.send_after(prompt, data).await,
+        }
+    })
+    .await
+    .expect("send");
+    *chunk = Some(before);
+}
+
+#[when(expr = "I run the script")]
+async fn when_i_run_the_script(
+    InteractionWorld {
+        buffer,
+        captures,
+        duplex,
+        interaction,
+        ..
+    }: &mut InteractionWorld,
+    step: &Step,
+) {
+    let script: Script = step
+        .docstring
+        .as_ref()
+        .expect("docstring")
+        .parse()
+        .expect("script");
+    *captures = Some(
+        feed(buffer, duplex, script.run(interaction))
+            .await
+            .expect("run"),
+    );
+}
+
+#[when(
+    regex = r"^I run the Interaction, answering each leak with its value plus (\d+)(?:, echoing (escaped|hexdump) output to a log)?$"
+)]
+async fn when_i_run_the_interaction_answering_each_leak(
+    InteractionWorld {
+        buffer,
+        duplex,
+        interaction,
+        log,
+        ..
+    }: &mut InteractionWorld,
+    offset: u64,
+    format: String,
+) {
+    let pattern = Regex::new("Leak: 0x([0-9a-f]+)").expect("regex");
+    let echo = match format.as_str() {
+        "" => Echo::default(),
+        format => {
+            let log = log.clone();
+            Echo::log(move |_, text| log.lock().unwrap().extend_from_slice(text)).format(
+                match format {
+                    "escaped" => Format::Escaped,
+                    "hexdump" => Format::Hexdump,
+                    _ => unreachable!(),
+                },
+            )
+        }
+    };
+    feed(
+        buffer,
+        duplex,
+        interaction.run_with_and_echo(
+            |chunk| match pattern.captures(chunk) {
+                Some(captures) => {
+                    let leak =
+                        u64::from_str_radix(std::str::from_utf8(&captures[1]).expect("UTF-8"), 16)
+                            .expect("hex");
+                    Action::SendLine(format!("{:#x}", leak + offset).into_bytes())
                 }
+                None => Action::Stop,
+            },
+            echo,
+        ),
+    )
+    .await
+    .expect("run");
+}
+
+#[when(expr = "I run the Interaction over a byte channel until the remote closes")]
+async fn when_i_run_the_interaction_over_a_byte_channel(
+    InteractionWorld {
+        buffer,
+        chunk,
+        duplex,
+        interaction,
+        ..
+    }: &mut InteractionWorld,
+) {
+    let (mut receiver, future) = interaction.run_with_byte_channel([]);
+    let (result, _) = join!(future, async {
+        feed(buffer, duplex, sleep(Duration::ZERO)).await;
+        duplex.shutdown().await.expect("shutdown");
+    });
+    result.expect("run");
+    let mut bytes = Vec::new();
+    while let Ok(mut received) = receiver.try_recv() {
+        bytes.append(&mut received);
+    }
+    *chunk = Some(bytes);
+}
+
+#[when(
+    regex = r#"^I read (?:until "(.*)"|a line|exactly (\d+) bytes)(?: within (\d+) timeouts?)?$"#
+)]
+async fn when_i_read_until(
+    InteractionWorld {
+        buffer,
+        chunk,
+        duplex,
+        error,
+        interaction,
+        ..
+    }: &mut InteractionWorld,
+    delimiter: String,
+    n: String,
+    deadline: String,
+) {
+    let deadline = (!deadline.is_empty())
+        .then(|| TestInteraction::TIMEOUT * deadline.parse::<u32>().expect("u32"));
+    match feed(buffer, duplex, async {
+        if !n.is_empty() {
+            interaction
+                .read_exact_n(n.parse().expect("usize"), deadline)
+                .await
+        } else if !delimiter.is_empty() {
+            interaction.read_until(delimiter.as_bytes(), deadline).await
+        } else {
+            interaction.read_line(deadline).await
+        }
+    })
+    .await
+    {
+        Ok(bytes) => *chunk = Some(bytes),
+        Err(e) => store_incomplete(e, chunk, error),
+    }
+}
+
+#[when(regex = r#"^I expect one of(?: within (\d+) timeouts?)?( ignoring case)?$"#)]
+async fn when_i_expect_one_of(
+    InteractionWorld {
+        buffer,
+        chunk,
+        duplex,
+        error,
+        expected,
+        interaction,
+        ..
+    }: &mut InteractionWorld,
+    deadline: String,
+    ignoring_case: String,
+    step: &Step,
+) {
+    let deadline = (!deadline.is_empty())
+        .then(|| TestInteraction::TIMEOUT * deadline.parse::<u32>().expect("u32"));
+    let patterns = step
+        .table
+        .as_ref()
+        .expect("table")
+        .rows
+        .iter()
+        .map(|row| match ignoring_case.as_str() {
+            "" => Regex::new(&row[0]).expect("regex"),
+            _ => RegexBuilder::new(&row[0])
+                .case_insensitive(true)
+                .build()
+                .expect("regex")
+                .into(),
+        })
+        .collect::<Vec<_>>();
+    match feed(buffer, duplex, interaction.expect(&patterns, deadline)).await {
+        Ok(ok) => *expected = Some(ok),
+        Err(e) => store_incomplete(e, chunk, error),
+    }
+}
+
+fn store_incomplete(
+    e: Box<dyn Error + Send + Sync>,
+    chunk: &mut Option<Vec<u8>>,
+    error: &mut Option<io::ErrorKind>,
+) {
+    let incomplete = e.downcast::<Incomplete>().expect("expected `Incomplete`");
+    *error = Some(incomplete.kind());
+    *chunk = Some(incomplete.buffer);
+}
+
+#[when(regex = r"^I (peek at|clean|read (\d+) bytes from) the buffered Interaction$")]
+async fn when_i_use_the_buffered_interaction(
+    InteractionWorld {
+        buffer,
+        buffered,
+        chunk,
+        duplex,
+        ..
+    }: &mut InteractionWorld,
+    action: String,
+    n: String,
+) {
+    let buffered = buffered.as_mut().expect("buffered");
+    *chunk = Some(
+        feed(buffer, duplex, async {
+            match action.as_str() {
+                "peek at" => buffered.peek().await.expect("peek").to_vec(),
+                "clean" => buffered.clean().await,
+                _ => buffered
+                    .read_exact_n(n.parse().expect("usize"), None)
+                    .await
+                    .expect("read"),
             }
-        },
-        async {
-            *chunk = Some(match function.as_str() {
-                "a" => interaction.read_chunk().await.expect("chunk"),
-                "the last" => interaction.read_last_chunk().await,
-                _ => unreachable!(),
-            });
-        },
+        })
+        .await,
     );
 }
 
+#[when(
+    regex = r#"^I (read a chunk from|send the line "([^"]*)" after "([^"]*)" through) the recorded Interaction$"#
+)]
+async fn when_i_use_the_recorded_interaction(
+    InteractionWorld {
+        buffer,
+        chunk,
+        duplex,
+        recorder,
+        ..
+    }: &mut InteractionWorld,
+    action: String,
+    data: String,
+    prompt: String,
+) {
+    let recorder = recorder.as_mut().expect("recorder");
+    *chunk = Some(
+        feed(buffer, duplex, async {
+            if action.starts_with("read") {
+                recorder.read_chunk_bytes().await
+            } else {
+                recorder
+                    .send_line_after(prompt.as_bytes(), data.as_bytes())
+                    .await
+            }
+        })
+        .await
+        .expect("recorded"),
+    );
+}
+
+#[when(expr = "I close the recorded Interaction")]
+async fn when_i_close_the_recorded_interaction(
+    InteractionWorld { recorder, .. }: &mut InteractionWorld,
+) {
+    recorder
+        .take()
+        .expect("recorder")
+        .close()
+        .await
+        .expect("close");
+}
+
+#[when(expr = "I read a chunk from the replay")]
+async fn when_i_read_a_chunk_from_the_replay(
+    InteractionWorld { chunk, replay, .. }: &mut InteractionWorld,
+) {
+    *chunk = Some(
+        replay
+            .as_mut()
+            .expect("replay")
+            .read_chunk_bytes()
+            .await
+            .expect("chunk"),
+    );
+}
+
+#[when(expr = "I send the line {string} to the replay")]
+async fn when_i_send_the_line_to_the_replay(
+    InteractionWorld { error, replay, .. }: &mut InteractionWorld,
+    data: String,
+) {
+    if let Err(e) = replay
+        .as_mut()
+        .expect("replay")
+        .send_line(data.as_bytes())
+        .await
+    {
+        *error = Some(e.downcast::<io::Error>().expect("io::Error").kind());
+    }
+}
+
+#[when(expr = "I unread {string}")]
+fn when_i_unread(InteractionWorld { buffered, .. }: &mut InteractionWorld, string: String) {
+    buffered
+        .as_mut()
+        .expect("buffered")
+        .unread(string.as_bytes())
+}
+
 #[then(expr = "the chunk I read should equal {string}")]
 fn then_the_chunk_i_read_should_equal(
     InteractionWorld { chunk, .. }: &mut InteractionWorld,
     string: String,
 ) {
-    assert_eq!(&string, chunk.as_ref().expect("chunk hasn't been read yet"))
+    assert_eq!(
+        string.replace("\\n", "\n").as_bytes(),
+        chunk.as_ref().expect("chunk hasn't been read yet")
+    )
+}
+
+#[then(expr = "the bytes I read should equal the hex bytes {string}")]
+fn then_the_bytes_i_read_should_equal(
+    InteractionWorld { chunk, .. }: &mut InteractionWorld,
+    hex: String,
+) {
+    assert_eq!(
+        &hex_to_bytes(&hex),
+        chunk.as_ref().expect("chunk hasn't been read yet")
+    )
+}
+
+#[then(expr = "the read should time out")]
+fn then_the_read_should_time_out(InteractionWorld { error, .. }: &mut InteractionWorld) {
+    assert_eq!(Some(io::ErrorKind::TimedOut), *error)
+}
+
+#[then(expr = "the replay should reject the write")]
+fn then_the_replay_should_reject_the_write(InteractionWorld { error, .. }: &mut InteractionWorld) {
+    assert_eq!(Some(io::ErrorKind::InvalidData), *error)
+}
+
+#[then(regex = "^the replay should (not )?close cleanly$")]
+async fn then_the_replay_should_close_cleanly(
+    InteractionWorld { replay, .. }: &mut InteractionWorld,
+    not: String,
+) {
+    assert_eq!(
+        not.is_empty(),
+        replay.take().expect("replay").close().await.is_ok()
+    )
+}
+
+#[then(expr = "the buffered Interaction should have {int} bytes pending")]
+fn then_the_buffered_interaction_should_have_bytes_pending(
+    InteractionWorld { buffered, .. }: &mut InteractionWorld,
+    n: usize,
+) {
+    assert_eq!(n, buffered.as_ref().expect("buffered").pending().len())
+}
+
+#[then(expr = "the remote should receive {string}")]
+async fn then_the_remote_should_receive(
+    InteractionWorld { duplex, .. }: &mut InteractionWorld,
+    string: String,
+) {
+    let expected = string.replace("\\n", "\n");
+    let mut received = vec![0; expected.len()];
+    duplex.read_exact(&mut received).await.expect("read");
+    assert_eq!(expected.as_bytes(), received);
+}
+
+#[then(expr = "the capture {string} should equal {string}")]
+fn then_the_capture_should_equal(
+    InteractionWorld { captures, .. }: &mut InteractionWorld,
+    name: String,
+    string: String,
+) {
+    assert_eq!(
+        Some(string.as_bytes()),
+        captures
+            .as_ref()
+            .expect("captures")
+            .get(&name)
+            .map(Vec::as_slice)
+    )
+}
+
+#[then(expr = "the log should read")]
+fn then_the_log_should_read(InteractionWorld { log, .. }: &mut InteractionWorld, step: &Step) {
+    assert_eq!(
+        step.docstring
+            .as_ref()
+            .expect("docstring")
+            .trim_start_matches('\n'),
+        String::from_utf8_lossy(&log.lock().unwrap())
+    )
+}
+
+#[then(expr = "the transcript should contain")]
+fn then_the_transcript_should_contain(
+    InteractionWorld { log, .. }: &mut InteractionWorld,
+    step: &Step,
+) {
+    let records = String::from_utf8(log.lock().unwrap().clone())
+        .expect("UTF-8")
+        .lines()
+        .map(|line| line.parse::<Record>().expect("record"))
+        .collect::<Vec<_>>();
+    assert!(records.is_sorted_by_key(|record| record.time));
+    assert_eq!(
+        step.table
+            .as_ref()
+            .expect("table")
+            .rows
+            .iter()
+            .map(|row| (row[0].clone(), row[1].clone()))
+            .collect::<Vec<_>>(),
+        records
+            .iter()
+            .map(|record| (
+                format!("{:?}", record.direction).to_lowercase(),
+                String::from_utf8_lossy(&record.data).into_owned()
+            ))
+            .collect::<Vec<_>>()
+    )
+}
+
+#[then(expr = "the timeout should be {int} milliseconds")]
+fn then_the_timeout_should_be(
+    InteractionWorld { interaction, .. }: &mut InteractionWorld,
+    millis: u128,
+) {
+    assert_eq!(millis, interaction.timing().timeout.as_millis())
+}
+
+#[then(expr = "pattern {int} should have matched")]
+fn then_pattern_should_have_matched(
+    InteractionWorld { expected, .. }: &mut InteractionWorld,
+    index: usize,
+) {
+    assert_eq!(index, expected.as_ref().expect("expected").index)
+}
+
+#[then(expr = "capture group {int} should equal {string}")]
+fn then_capture_group_should_equal(
+    InteractionWorld { expected, .. }: &mut InteractionWorld,
+    index: usize,
+    string: String,
+) {
+    assert_eq!(
+        Some(string.as_bytes()),
+        expected.as_ref().expect("expected").group(index)
+    )
+}
+
+#[then(expr = "the data before the match should equal {string}")]
+fn then_the_data_before_the_match_should_equal(
+    InteractionWorld { expected, .. }: &mut InteractionWorld,
+    string: String,
+) {
+    assert_eq!(
+        string.replace("\\n", "\n").as_bytes(),
+        expected.as_ref().expect("expected").before
+    )
 }
 
 #[tokio::main]
@@ -0,0 +1,483 @@
+use cucumber::{World, given, then, when};
+use libspl::{
+    Interaction, PID,
+    interaction::{
+        pty::Pty,
+        ssh::{Builder, Connection, Forward, KnownHosts, SSH, SessionBuilder},
+    },
+};
+use std::{
+    env, fs,
+    net::SocketAddr,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::{self, Command, Output, Stdio},
+    time::Duration,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+    time,
+};
+
+/// Where the SSH server is expected to be, unless `SSHD` says otherwise.
+const SSHD: &str = "/usr/sbin/sshd";
+
+#[derive(Debug, Default, World)]
+struct SshWorld {
+    connection: Option<Connection>,
+    /// A temporary directory for the server's keys and the transferred files.
+    directory: PathBuf,
+    /// Why the last connection or transfer failed.
+    error: Option<String>,
+    forward: Option<Forward>,
+    /// The local address of the last forward, once it has been closed.
+    forwarded: Option<SocketAddr>,
+    output: Option<Output>,
+    /// The PID and process group of the last process to be closed.
+    pid: Option<(u32, u32)>,
+    port: u16,
+    /// Spawned processes, in order. Closed processes are left as `None`.
+    processes: Vec<Option<SSH>>,
+    server: Option<process::Child>,
+    service: Option<SocketAddr>,
+}
+impl SshWorld {
+    fn connection(&self) -> &Connection {
+        self.connection
+            .as_ref()
+            .unwrap_or_else(|| panic!("not connected: {:?}", self.error))
+    }
+
+    fn process(&mut self, index: usize) -> &mut SSH {
+        self.processes[index - 1].as_mut().expect("process")
+    }
+}
+
+fn sshd() -> PathBuf {
+    env::var_os("SSHD").map_or_else(|| SSHD.into(), PathBuf::from)
+}
+
+fn keygen(path: &Path) {
+    let status = Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-C", "", "-f"])
+        .arg(path)
+        .status()
+        .expect("ssh-keygen");
+    assert!(status.success());
+}
+
+/// Reads the state and process group of `pid`.
+fn stat(pid: u32) -> Option<(char, u32)> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    let mut fields = stat.rsplit_once(')')?.1.split_whitespace();
+    let state = fields.next()?.chars().next()?;
+    Some((state, fields.nth(1)?.parse().ok()?))
+}
+
+#[given(regex = r#"^an SSH server(?: that prints "(.*)" on login)?(?: without "(.*)")?$"#)]
+async fn given_an_ssh_server(world: &mut SshWorld, banner: String, missing: String) {
+    world.port = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("port")
+        .port();
+    world.directory = env::temp_dir().join(format!("libspl-ssh-{}-{}", process::id(), world.port));
+    fs::create_dir_all(&world.directory).expect("directory");
+
+    let directory = &world.directory;
+    for key in ["host", "id", "other"] {
+        keygen(&directory.join(key));
+    }
+    fs::copy(directory.join("id.pub"), directory.join("authorized_keys")).expect("copy");
+    let mut config = format!(
+        "ListenAddress 127.0.0.1\nPort {}\nHostKey {}\nAuthorizedKeysFile {}\nPidFile none\n\
+         StrictModes no\nUsePAM no\nPasswordAuthentication no\nKbdInteractiveAuthentication no\n\
+         AllowTcpForwarding yes\n",
+        world.port,
+        directory.join("host").display(),
+        directory.join("authorized_keys").display(),
+    );
+    // Commands are run by the user's shell, through `ForceCommand` to change what it does first.
+    let mut prefix = String::new();
+    if !banner.is_empty() {
+        prefix += &format!("echo '{banner}'; ");
+    }
+    if !missing.is_empty() {
+        let bin = directory.join("bin");
+        fs::create_dir_all(&bin).expect("directory");
+        for entry in ["/bin", "/usr/bin"]
+            .iter()
+            .flat_map(|path| fs::read_dir(path).expect("bin"))
+        {
+            let entry = entry.expect("entry");
+            if entry.file_name() != missing.as_str() {
+                let _ = std::os::unix::fs::symlink(entry.path(), bin.join(entry.file_name()));
+            }
+        }
+        prefix += &format!("PATH={}; ", bin.display());
+    }
+    if !prefix.is_empty() {
+        config += &format!("ForceCommand {prefix}eval \"$SSH_ORIGINAL_COMMAND\"\n");
+    }
+    fs::write(directory.join("sshd_config"), config).expect("config");
+
+    let log = directory.join("sshd.log");
+    world.server = Some(
+        Command::new(sshd())
+            .arg("-D")
+            .arg("-e")
+            .arg("-f")
+            .arg(directory.join("sshd_config"))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(fs::File::create(&log).expect("log"))
+            .spawn()
+            .expect("sshd"),
+    );
+    for _ in 0..100 {
+        if TcpStream::connect(("127.0.0.1", world.port)).await.is_ok() {
+            return;
+        }
+        time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!(
+        "sshd did not start: {}",
+        fs::read_to_string(log).unwrap_or_default()
+    )
+}
+
+#[given(expr = "a local file {string} containing {string} with mode {word}")]
+fn given_a_local_file(
+    SshWorld { directory, .. }: &mut SshWorld,
+    name: String,
+    contents: String,
+    mode: String,
+) {
+    let path = directory.join(name);
+    fs::write(&path, contents).expect("write");
+    let mode = u32::from_str_radix(&mode, 8).expect("mode");
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).expect("chmod");
+}
+
+#[given(expr = "a TCP service that sends {string}")]
+async fn given_a_tcp_service(SshWorld { service, .. }: &mut SshWorld, line: String) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    *service = Some(listener.local_addr().expect("address"));
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.expect("accept");
+            let _ = stream.write_all(format!("{line}\n").as_bytes()).await;
+        }
+    });
+}
+
+#[when(
+    regex = r"^I connect to the SSH server(, checking its host key| with another identity| through itself as a jump host| through a jump host that is not listening)?$"
+)]
+async fn when_i_connect_to_the_ssh_server(world: &mut SshWorld, options: String) {
+    let user = Command::new("id").arg("-un").output().expect("id").stdout;
+    let user = String::from_utf8_lossy(&user).trim().to_owned();
+    let directory = &world.directory;
+    // Jump hosts only get the options from the SSH configuration.
+    let mut session = SessionBuilder::default();
+    if options.contains("jump host") {
+        let config = directory.join("ssh_config");
+        fs::write(
+            &config,
+            format!(
+                "Host *\n  User {user}\n  IdentityFile {}\n  IdentitiesOnly yes\n  \
+                 StrictHostKeyChecking no\n  UserKnownHostsFile /dev/null\n",
+                directory.join("id").display()
+            ),
+        )
+        .expect("config");
+        session.config_file(config);
+    }
+    let builder = Builder::from(session)
+        .port(world.port)
+        .user(user)
+        .control_dir(directory)
+        .connect_timeout(Duration::from_secs(5));
+    let builder = match options.as_str() {
+        "" => builder.insecure().identity(directory.join("id")),
+        ", checking its host key" => {
+            fs::write(directory.join("known_hosts"), "").expect("known hosts");
+            builder
+                .known_hosts(KnownHosts::Strict)
+                .known_hosts_file(directory.join("known_hosts"))
+                .identity(directory.join("id"))
+        }
+        " with another identity" => builder.insecure().identity(directory.join("other")),
+        " through itself as a jump host" => builder
+            .insecure()
+            .identity(directory.join("id"))
+            .jump_host(format!("localhost:{}", world.port)),
+        _ => {
+            let closed = std::net::TcpListener::bind("127.0.0.1:0")
+                .and_then(|listener| listener.local_addr())
+                .expect("port");
+            builder
+                .insecure()
+                .identity(directory.join("id"))
+                .jump_host(closed.to_string())
+        }
+    };
+    world.connection = match builder.connect("127.0.0.1").await {
+        Ok(session) => Some(session.into()),
+        Err(error) => {
+            world.error = Some(error.to_string());
+            None
+        }
+    };
+}
+
+#[when(
+    regex = r#"^I spawn "(.*)" over the SSH connection(?: in a (raw|cooked) pseudo-terminal)?$"#
+)]
+async fn when_i_spawn(world: &mut SshWorld, file: String, pty: String) {
+    let connection = world.connection();
+    let process = match pty.as_str() {
+        "" => connection.spawn(file).await,
+        "raw" => connection.spawn_pty(file, Pty::RAW).await,
+        _ => connection.spawn_pty(file, Pty::COOKED).await,
+    }
+    .expect("spawn");
+    world.processes.push(Some(process));
+}
+
+#[when(expr = "I send the line {string} to SSH process {int}")]
+async fn when_i_send_the_line(world: &mut SshWorld, line: String, index: usize) {
+    world
+        .process(index)
+        .send_line(line.as_bytes())
+        .await
+        .expect("send");
+}
+
+#[when(expr = "I name SSH process {int} {string}")]
+fn when_i_name_ssh_process(world: &mut SshWorld, index: usize, name: String) {
+    world.process(index).set_name(name);
+}
+
+#[when(expr = "I close SSH process {int}")]
+async fn when_i_close_ssh_process(world: &mut SshWorld, index: usize) {
+    let process = world.processes[index - 1].take().expect("process");
+    let pid = process.get_pid().await.expect("PID");
+    world.pid = Some((pid, stat(pid).expect("stat").1));
+    process.close().await.expect("close");
+}
+
+#[when(expr = "I run {string} over the SSH connection")]
+async fn when_i_run(world: &mut SshWorld, command: String) {
+    let connection = world.connection();
+    world.output = Some(connection.run(command).await.expect("run"));
+}
+
+#[when(expr = "I upload {string} to {string}")]
+async fn when_i_upload(world: &mut SshWorld, local: String, remote: String) {
+    let connection = world.connection();
+    let remote = world.directory.join(remote);
+    if let Err(error) = connection
+        .upload(world.directory.join(local), remote.to_str().expect("path"))
+        .await
+    {
+        world.error = Some(error.to_string());
+    }
+}
+
+#[when(expr = "I download {string} to {string}")]
+async fn when_i_download(world: &mut SshWorld, remote: String, local: String) {
+    let connection = world.connection();
+    let remote = world.directory.join(remote);
+    if let Err(error) = connection
+        .download(remote.to_str().expect("path"), world.directory.join(local))
+        .await
+    {
+        world.error = Some(error.to_string());
+    }
+}
+
+#[when(expr = "I forward a connection to the TCP service")]
+async fn when_i_forward_a_connection(world: &mut SshWorld) {
+    let connection = world.connection();
+    let service = world.service.expect("service");
+    let forward = connection
+        .forward(service.ip().to_string(), service.port())
+        .await
+        .expect("forward");
+    world.forward = Some(forward);
+}
+
+#[when(expr = "I close the forwarded connection")]
+async fn when_i_close_the_forwarded_connection(world: &mut SshWorld) {
+    let forward = world.forward.take().expect("forward");
+    world.forwarded = Some(forward.peer_addr().expect("address"));
+    forward.close().await.expect("close");
+}
+
+#[then(expr = "the SSH connection should fail")]
+fn then_the_ssh_connection_should_fail(SshWorld { connection, .. }: &mut SshWorld) {
+    assert!(connection.is_none())
+}
+
+#[then(expr = "SSH process {int} should receive the line {string}")]
+async fn then_ssh_process_should_receive(world: &mut SshWorld, index: usize, line: String) {
+    assert_eq!(
+        format!("{line}\n").into_bytes(),
+        world
+            .process(index)
+            .read_line(Some(Duration::from_secs(5)))
+            .await
+            .expect("line")
+    )
+}
+
+#[then(expr = "SSH process {int} should still echo lines")]
+async fn then_ssh_process_should_still_echo_lines(world: &mut SshWorld, index: usize) {
+    let process = world.process(index);
+    process.send_line(b"again").await.expect("send");
+    assert_eq!(
+        b"again\n".to_vec(),
+        process
+            .read_line(Some(Duration::from_secs(5)))
+            .await
+            .expect("line")
+    )
+}
+
+#[then(expr = "the command should print {string}")]
+fn then_the_command_should_print(SshWorld { output, .. }: &mut SshWorld, line: String) {
+    assert_eq!(
+        format!("{line}\n").into_bytes(),
+        output.take().expect("output").stdout
+    )
+}
+
+#[then(expr = "the PID of SSH process {int} should belong to {string}")]
+async fn then_the_pid_should_belong_to(world: &mut SshWorld, index: usize, name: String) {
+    let comm = format!(
+        "/proc/{}/comm",
+        world.process(index).get_pid().await.expect("PID")
+    );
+    // The launch wrappers may not have been replaced by the target yet.
+    for _ in 0..20 {
+        if fs::read_to_string(&comm).expect("comm").trim() == name {
+            return;
+        }
+        time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("{comm} is {}", fs::read_to_string(&comm).expect("comm"))
+}
+
+#[then(expr = "the closed process should no longer be running")]
+async fn then_the_closed_process_should_no_longer_be_running(SshWorld { pid, .. }: &mut SshWorld) {
+    let (pid, _) = pid.expect("PID");
+    for _ in 0..20 {
+        // Killed processes linger as zombies until they are reaped.
+        match stat(pid) {
+            Some((state, _)) if state != 'Z' => {}
+            _ => return,
+        }
+        time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("{pid} is still running")
+}
+
+#[then(expr = "no process of the closed process group should be running")]
+async fn then_no_process_of_the_group_should_be_running(SshWorld { pid, .. }: &mut SshWorld) {
+    let (_, group) = pid.expect("PID");
+    let running = || {
+        fs::read_dir("/proc")
+            .expect("proc")
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+            .filter(
+                |&pid| matches!(stat(pid), Some((state, pgrp)) if state != 'Z' && pgrp == group),
+            )
+            .collect::<Vec<u32>>()
+    };
+    for _ in 0..20 {
+        if running().is_empty() {
+            return;
+        }
+        time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("{:?} are still running in group {group}", running())
+}
+
+#[then(expr = "the file {string} should contain {string} with mode {word}")]
+fn then_the_file_should_contain(
+    SshWorld {
+        directory, error, ..
+    }: &mut SshWorld,
+    name: String,
+    contents: String,
+    mode: String,
+) {
+    assert_eq!(None, *error);
+    let path = directory.join(name);
+    assert_eq!(contents, fs::read_to_string(&path).expect("read"));
+    assert_eq!(
+        u32::from_str_radix(&mode, 8).expect("mode"),
+        fs::metadata(path).expect("metadata").permissions().mode() & 0o7777
+    )
+}
+
+#[then(expr = "the transfer should fail with {string}")]
+fn then_the_transfer_should_fail_with(SshWorld { error, .. }: &mut SshWorld, message: String) {
+    let error = error.take().expect("error");
+    assert!(error.contains(&message), "{error}")
+}
+
+#[then(expr = "the forwarded connection should receive the line {string}")]
+async fn then_the_forwarded_connection_should_receive(
+    SshWorld { forward, .. }: &mut SshWorld,
+    line: String,
+) {
+    assert_eq!(
+        format!("{line}\n").into_bytes(),
+        forward
+            .as_mut()
+            .expect("forward")
+            .read_line(Some(Duration::from_secs(5)))
+            .await
+            .expect("line")
+    )
+}
+
+#[then(expr = "the forward should no longer accept connections")]
+async fn then_the_forward_should_no_longer_accept(SshWorld { forwarded, .. }: &mut SshWorld) {
+    assert!(
+        TcpStream::connect(forwarded.expect("address"))
+            .await
+            .is_err()
+    )
+}
+
+#[tokio::main]
+async fn main() {
+    if env::var_os("SSHD").is_none() && !Path::new(SSHD).exists() {
+        eprintln!("Skipping the SSH tests, since there is no sshd at {SSHD} (see SSHD)");
+        return;
+    }
+    SshWorld::cucumber()
+        .fail_on_skipped()
+        .after(|_, _, _, _, world| {
+            Box::pin(async move {
+                if let Some(world) = world {
+                    // Processes and forwards hold handles to the connection too.
+                    world.processes.clear();
+                    world.forward = None;
+                    if let Some(connection) = world.connection.take() {
+                        let _ = connection.close().await;
+                    }
+                    if let Some(mut server) = world.server.take() {
+                        let _ = server.kill();
+                        let _ = server.wait();
+                    }
+                    let _ = fs::remove_dir_all(&world.directory);
+                }
+            })
+        })
+        .run_and_exit("./features/ssh.feature")
+        .await
+}
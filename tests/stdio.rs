@@ -0,0 +1,317 @@
+use cucumber::{World, given, then, when};
+use libspl::{
+    Interaction, PID,
+    interaction::{
+        AnyInteraction,
+        pty::Pty,
+        stdio::{self, Builder, Stderr, Stdio},
+    },
+};
+use std::{
+    fs::{self, File},
+    os::fd::AsRawFd,
+    path::PathBuf,
+    process::{self, Command},
+    time::Duration,
+};
+use tokio::{io::AsyncReadExt, time};
+
+#[derive(Debug, Default, World)]
+struct StdioWorld {
+    /// The process, once wrapped in an [`AnyInteraction`].
+    any: Option<AnyInteraction>,
+    builder: Option<Builder>,
+    /// A temporary directory, to be removed after the scenario.
+    directory: Option<PathBuf>,
+    libc: PathBuf,
+    loader: PathBuf,
+    process: Option<Stdio>,
+}
+
+/// Splits `command` into words, keeping single-quoted strings together.
+fn words(command: &str) -> Vec<String> {
+    command
+        .split('\'')
+        .enumerate()
+        .flat_map(|(i, part)| {
+            if i % 2 == 1 {
+                vec![part.to_owned()]
+            } else {
+                part.split_whitespace().map(str::to_owned).collect()
+            }
+        })
+        .collect()
+}
+
+#[given(
+    regex = r#"^the process "(.*)"(?: in an? (raw|cooked) pseudo-terminal)?(?: with (merged|separate|discarded) stderr)?$"#
+)]
+async fn given_the_process(
+    StdioWorld { process, .. }: &mut StdioWorld,
+    command: String,
+    pty: String,
+    stderr: String,
+) {
+    let words = words(&command);
+    let (path, arguments) = words.split_first().expect("command");
+    let stderr = match stderr.as_str() {
+        "merged" => Stderr::Merge,
+        "separate" => Stderr::Separate,
+        "discarded" => Stderr::Discard,
+        _ => Stderr::Inherit,
+    };
+    *process = Some(if stderr == Stderr::Inherit {
+        match pty.as_str() {
+            "" => stdio::interact(path, Some(arguments)).await,
+            "raw" => stdio::interact_pty(path, Some(arguments), Pty::RAW).await,
+            _ => stdio::interact_pty(path, Some(arguments), Pty::COOKED).await,
+        }
+        .expect("spawn")
+    } else {
+        let builder = Builder::new(path).args(arguments).stderr(stderr);
+        match pty.as_str() {
+            "" => builder,
+            "raw" => builder.pty(Pty::RAW),
+            _ => builder.pty(Pty::COOKED),
+        }
+        .spawn()
+        .expect("spawn")
+    });
+}
+
+#[given(expr = "the executable {string}")]
+async fn given_the_executable(StdioWorld { builder, .. }: &mut StdioWorld, command: String) {
+    let words = words(&command);
+    let (path, arguments) = words.split_first().expect("command");
+    *builder = Some(Builder::new(path).args(arguments));
+}
+
+/// Applies `f` to the [`Builder`] in `world`.
+fn configure(world: &mut StdioWorld, f: impl FnOnce(Builder) -> Builder) {
+    world.builder = Some(f(world.builder.take().expect("builder")));
+}
+
+#[given(expr = "the environment variable {string} set to {string}")]
+async fn given_the_environment_variable(world: &mut StdioWorld, key: String, value: String) {
+    configure(world, |builder| builder.env(key, value));
+}
+
+#[given(expr = "the environment variable {string} removed")]
+async fn given_the_environment_variable_removed(world: &mut StdioWorld, key: String) {
+    configure(world, |builder| builder.env_remove(key));
+}
+
+#[given("a cleared environment")]
+async fn given_a_cleared_environment(world: &mut StdioWorld) {
+    configure(world, Builder::env_clear);
+}
+
+#[given(expr = "the preloaded library {string}")]
+async fn given_the_preloaded_library(world: &mut StdioWorld, library: String) {
+    configure(world, |builder| builder.preload(library));
+}
+
+#[given(expr = "the working directory {string}")]
+async fn given_the_working_directory(world: &mut StdioWorld, directory: String) {
+    configure(world, |builder| builder.current_dir(directory));
+}
+
+#[given(regex = r#"^argv\[0\] set to "(.*)"$"#)]
+async fn given_argv0(world: &mut StdioWorld, arg0: String) {
+    configure(world, |builder| builder.arg0(arg0));
+}
+
+#[given(expr = "the umask {word}")]
+async fn given_the_umask(world: &mut StdioWorld, mask: String) {
+    let mask = u32::from_str_radix(&mask, 8).expect("octal");
+    configure(world, |builder| builder.umask(mask));
+}
+
+#[given(expr = "file descriptor {int} open on {string}")]
+async fn given_file_descriptor(world: &mut StdioWorld, target: i32, path: String) {
+    let file = File::open(path).expect("open");
+    configure(world, |builder| builder.fd(target, file));
+}
+
+/// Also sets `$FIRST` and `$SECOND` to the file descriptors the two files end up in.
+#[given(expr = "{string} and {string} passed on in each other's file descriptors")]
+async fn given_swapped_file_descriptors(world: &mut StdioWorld, first: String, second: String) {
+    let (first, second) = (
+        File::open(first).expect("open"),
+        File::open(second).expect("open"),
+    );
+    let (a, b) = (first.as_raw_fd(), second.as_raw_fd());
+    configure(world, |builder| {
+        builder
+            .env("FIRST", b.to_string())
+            .env("SECOND", a.to_string())
+            .fd(b, first)
+            .fd(a, second)
+    });
+}
+
+#[given(regex = r"^the system's libc( copied to a new directory)? and loader$")]
+async fn given_the_systems_libc(world: &mut StdioWorld, copied: String) {
+    let ldd = Command::new("ldd").arg("/bin/sh").output().expect("ldd");
+    for line in String::from_utf8_lossy(&ldd.stdout).lines() {
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["libc.so.6", "=>", path, ..] => world.libc = path.into(),
+            [path, ..] if path.starts_with('/') => world.loader = path.into(),
+            _ => (),
+        }
+    }
+    if !copied.is_empty() {
+        let directory = std::env::temp_dir().join(format!(
+            "libspl-stdio-{}-{}",
+            process::id(),
+            rand::random::<u32>()
+        ));
+        fs::create_dir_all(&directory).expect("directory");
+        fs::copy(&world.libc, directory.join("libc.so.6")).expect("copy");
+        world.libc = directory.join("libc.so.6");
+        world.directory = Some(directory);
+    }
+    let (libc, loader) = (world.libc.clone(), world.loader.clone());
+    configure(world, |builder| builder.libc(libc, loader));
+}
+
+#[given("discarded stderr")]
+async fn given_discarded_stderr(world: &mut StdioWorld) {
+    configure(world, |builder| builder.stderr(Stderr::Discard));
+}
+
+#[when("I launch the process")]
+async fn when_i_launch_the_process(
+    StdioWorld {
+        builder, process, ..
+    }: &mut StdioWorld,
+) {
+    *process = Some(builder.take().expect("builder").spawn().expect("spawn"));
+}
+
+#[when(expr = "I send the line {string} to the process")]
+async fn when_i_send_the_line(StdioWorld { process, .. }: &mut StdioWorld, line: String) {
+    process
+        .as_mut()
+        .expect("process")
+        .send_line(line.replace("\\t", "\t").as_bytes())
+        .await
+        .expect("send");
+}
+
+#[when("I wrap the process in an AnyInteraction")]
+async fn when_i_wrap_the_process(StdioWorld { any, process, .. }: &mut StdioWorld) {
+    *any = Some(process.take().expect("process").into());
+}
+
+#[when(expr = "I send the line {string} to the wrapped process")]
+async fn when_i_send_the_line_to_the_wrapped_process(
+    StdioWorld { any, .. }: &mut StdioWorld,
+    line: String,
+) {
+    any.as_mut()
+        .expect("wrapped process")
+        .send_line(line.as_bytes())
+        .await
+        .expect("send");
+}
+
+#[then(expr = "the process should print the line {string}")]
+async fn then_the_process_should_print(StdioWorld { process, .. }: &mut StdioWorld, line: String) {
+    assert_eq!(
+        format!("{}\n", line.replace("\\t", "\t").replace("\\r", "\r")),
+        String::from_utf8_lossy(
+            &process
+                .as_mut()
+                .expect("process")
+                .read_line(Some(Duration::from_secs(1)))
+                .await
+                .expect("line")
+        )
+    )
+}
+
+#[then(expr = "the process should print {string} on stderr")]
+async fn then_the_process_should_print_on_stderr(
+    StdioWorld { process, .. }: &mut StdioWorld,
+    output: String,
+) {
+    let mut buffer = Vec::new();
+    time::timeout(
+        Duration::from_secs(1),
+        process
+            .as_mut()
+            .expect("process")
+            .stderr
+            .as_mut()
+            .expect("stderr")
+            .read_to_end(&mut buffer),
+    )
+    .await
+    .expect("timeout")
+    .expect("read");
+    assert_eq!(
+        output.replace("\\n", "\n"),
+        String::from_utf8_lossy(&buffer)
+    )
+}
+
+#[then(expr = "the wrapped process should print the line {string}")]
+async fn then_the_wrapped_process_should_print(
+    StdioWorld { any, .. }: &mut StdioWorld,
+    line: String,
+) {
+    assert_eq!(
+        format!("{line}\n"),
+        String::from_utf8_lossy(
+            &any.as_mut()
+                .expect("wrapped process")
+                .read_line(Some(Duration::from_secs(1)))
+                .await
+                .expect("line")
+        )
+    )
+}
+
+#[then("the wrapped process should print its PID")]
+async fn then_the_wrapped_process_should_print_its_pid(world: &mut StdioWorld) {
+    let pid = world
+        .any
+        .as_ref()
+        .expect("wrapped process")
+        .get_pid()
+        .await
+        .expect("PID");
+    then_the_wrapped_process_should_print(world, pid.to_string()).await
+}
+
+#[then("the process should have no stderr handle")]
+async fn then_the_process_should_have_no_stderr(StdioWorld { process, .. }: &mut StdioWorld) {
+    assert!(process.as_ref().expect("process").stderr.is_none())
+}
+
+#[then(regex = "^the process should print the path to the (libc|loader)$")]
+async fn then_the_process_should_print_the_path(world: &mut StdioWorld, file: String) {
+    let path = if file == "libc" {
+        &world.libc
+    } else {
+        &world.loader
+    };
+    let line = format!("{}", fs::canonicalize(path).expect("path").display());
+    then_the_process_should_print(world, line).await
+}
+
+#[tokio::main]
+async fn main() {
+    StdioWorld::cucumber()
+        .fail_on_skipped()
+        .after(|_, _, _, _, world| {
+            Box::pin(async move {
+                if let Some(directory) = world.and_then(|world| world.directory.as_ref()) {
+                    let _ = fs::remove_dir_all(directory);
+                }
+            })
+        })
+        .run_and_exit("./features/stdio.feature")
+        .await
+}
@@ -0,0 +1,93 @@
+use cucumber::{World, given, then, when};
+use libspl::{
+    Interaction,
+    interaction::tcp::{Listener, Tcp},
+};
+use std::{io, time::Duration};
+use tokio::{io::AsyncWriteExt, net::TcpStream};
+
+#[derive(Debug, Default, World)]
+struct TcpWorld {
+    accepted: Option<Tcp>,
+    client: Option<TcpStream>,
+    error: Option<io::ErrorKind>,
+    listener: Option<Listener>,
+}
+
+#[given(expr = "a TCP listener on {string}")]
+async fn given_a_tcp_listener_on(TcpWorld { listener, .. }: &mut TcpWorld, address: String) {
+    *listener = Some(Listener::bind(address).await.expect("bind"));
+}
+
+#[when(expr = "a client connects to the listener and sends {string}")]
+async fn when_a_client_connects(
+    TcpWorld {
+        client, listener, ..
+    }: &mut TcpWorld,
+    data: String,
+) {
+    let mut stream = TcpStream::connect(
+        listener
+            .as_ref()
+            .expect("listener")
+            .local_addr()
+            .expect("address"),
+    )
+    .await
+    .expect("connect");
+    stream
+        .write_all(data.replace("\\n", "\n").as_bytes())
+        .await
+        .expect("write");
+    *client = Some(stream);
+}
+
+#[when(regex = r"^I accept a connection(?: within (\d+) milliseconds)?$")]
+async fn when_i_accept_a_connection(
+    TcpWorld {
+        accepted,
+        error,
+        listener,
+        ..
+    }: &mut TcpWorld,
+    millis: String,
+) {
+    match listener
+        .as_ref()
+        .expect("listener")
+        .accept(millis.parse().ok().map(Duration::from_millis))
+        .await
+    {
+        Ok(tcp) => *accepted = Some(tcp),
+        Err(e) => *error = Some(e.kind()),
+    }
+}
+
+#[then(expr = "the accepted connection should receive the line {string}")]
+async fn then_the_accepted_connection_should_receive(
+    TcpWorld { accepted, .. }: &mut TcpWorld,
+    line: String,
+) {
+    assert_eq!(
+        line.replace("\\n", "\n").as_bytes(),
+        accepted
+            .as_mut()
+            .expect("accepted")
+            .read_line(Some(Duration::from_secs(1)))
+            .await
+            .expect("line")
+    )
+}
+
+#[then(expr = "the accept should time out")]
+fn then_the_accept_should_time_out(TcpWorld { error, .. }: &mut TcpWorld) {
+    assert_eq!(Some(io::ErrorKind::TimedOut), *error)
+}
+
+#[tokio::main]
+async fn main() {
+    TcpWorld::cucumber()
+        .fail_on_skipped()
+        .run_and_exit("./features/tcp.feature")
+        .await
+}
@@ -0,0 +1,141 @@
+use cucumber::{World, given, then, when};
+use libspl::{Interaction, interaction::tls::Builder};
+use rcgen::{CertifiedKey, generate_simple_self_signed};
+use std::{fs, net::SocketAddr, path::PathBuf, process, sync::Arc, time::Duration};
+use tokio::{io::AsyncWriteExt, net::TcpListener};
+use tokio_rustls::{
+    TlsAcceptor,
+    rustls::{
+        RootCertStore, ServerConfig,
+        crypto::ring,
+        pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer},
+        server::WebPkiClientVerifier,
+    },
+};
+
+#[derive(Debug, Default, World)]
+struct TlsWorld {
+    address: Option<SocketAddr>,
+    directory: PathBuf,
+    line: Option<Vec<u8>>,
+}
+impl TlsWorld {
+    /// Writes `contents` to a file called `name` in this scenario's directory.
+    fn write(&mut self, name: &str, contents: String) -> PathBuf {
+        let path = self.directory.join(name);
+        fs::write(&path, contents).expect("write");
+        path
+    }
+}
+
+fn private_key(certified: &CertifiedKey<rcgen::KeyPair>) -> PrivateKeyDer<'static> {
+    PrivatePkcs8KeyDer::from(certified.signing_key.serialize_der()).into()
+}
+
+#[given(regex = r#"^a TLS server for "([^"]*)"( requiring a client certificate)?$"#)]
+async fn given_a_tls_server(world: &mut TlsWorld, name: String, client_auth: String) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let address = listener.local_addr().expect("address");
+    world.address = Some(address);
+    world.directory =
+        std::env::temp_dir().join(format!("libspl-tls-{}-{}", process::id(), address.port()));
+    fs::create_dir_all(&world.directory).expect("directory");
+
+    let server = generate_simple_self_signed(vec![name]).expect("certificate");
+    world.write("server.pem", server.cert.pem());
+    let client = generate_simple_self_signed(vec!["client".into()]).expect("certificate");
+    world.write("client.pem", client.cert.pem());
+    world.write("client.key", client.signing_key.serialize_pem());
+
+    let provider = Arc::new(ring::default_provider());
+    let config = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .expect("protocol versions");
+    let config = if client_auth.is_empty() {
+        config.with_no_client_auth()
+    } else {
+        let mut roots = RootCertStore::empty();
+        roots.add(client.cert.der().clone()).expect("root");
+        config.with_client_cert_verifier(
+            WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .expect("verifier"),
+        )
+    }
+    .with_single_cert(vec![server.cert.der().clone()], private_key(&server))
+    .expect("config");
+
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.expect("accept");
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                if let Ok(mut stream) = acceptor.accept(stream).await {
+                    let name = stream.get_ref().1.server_name().unwrap_or("").to_owned();
+                    let _ = stream.write_all(format!("hello {name}\n").as_bytes()).await;
+                    let _ = stream.flush().await;
+                }
+            });
+        }
+    });
+}
+
+#[when(
+    regex = r#"^I connect to the TLS server as "([^"]*)"(, trusting its certificate|, without verification)?( with a client certificate)?$"#
+)]
+async fn when_i_connect_to_the_tls_server(
+    world: &mut TlsWorld,
+    name: String,
+    verification: String,
+    client_auth: String,
+) {
+    let mut builder = Builder::new().server_name(name);
+    if verification.contains("trusting") {
+        builder = builder.ca(world.directory.join("server.pem")).expect("CA");
+    } else if !verification.is_empty() {
+        builder = builder.insecure();
+    }
+    if !client_auth.is_empty() {
+        builder = builder
+            .client_cert(
+                world.directory.join("client.pem"),
+                world.directory.join("client.key"),
+            )
+            .expect("client certificate");
+    }
+
+    // With TLS 1.3, a rejected client certificate is only reported once the client reads.
+    world.line = match builder
+        .connect(&world.address.expect("address").to_string())
+        .await
+    {
+        Ok(mut tls) => tls.read_line(Some(Duration::from_secs(1))).await.ok(),
+        Err(_) => None,
+    };
+}
+
+#[then(expr = "the TLS connection should receive the line {string}")]
+fn then_the_tls_connection_should_receive(TlsWorld { line, .. }: &mut TlsWorld, expected: String) {
+    assert_eq!(Some(format!("{expected}\n").into_bytes()), *line)
+}
+
+#[then(expr = "the TLS connection should fail")]
+fn then_the_tls_connection_should_fail(TlsWorld { line, .. }: &mut TlsWorld) {
+    assert_eq!(None, *line)
+}
+
+#[tokio::main]
+async fn main() {
+    TlsWorld::cucumber()
+        .fail_on_skipped()
+        .after(|_, _, _, _, world| {
+            Box::pin(async move {
+                if let Some(world) = world {
+                    let _ = fs::remove_dir_all(&world.directory);
+                }
+            })
+        })
+        .run_and_exit("./features/tls.feature")
+        .await
+}
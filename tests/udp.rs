@@ -0,0 +1,84 @@
+use cucumber::{World, given, then, when};
+use libspl::{
+    Interaction,
+    interaction::udp::{self, Udp},
+};
+use tokio::{io::AsyncWriteExt, net::UdpSocket};
+
+#[derive(Debug, Default, World)]
+struct UdpWorld {
+    received: Option<Vec<u8>>,
+    udp: Option<Udp>,
+}
+
+#[given(expr = "a UDP server that answers each datagram with the datagram and {string}")]
+async fn given_a_udp_server(UdpWorld { udp, .. }: &mut UdpWorld, suffix: String) {
+    let server = UdpSocket::bind("127.0.0.1:0").await.expect("bind");
+    let address = server.local_addr().expect("address");
+    tokio::spawn(async move {
+        let mut buf = [0; 1024];
+        loop {
+            let (n, peer) = server.recv_from(&mut buf).await.expect("receive");
+            server.send_to(&buf[..n], peer).await.expect("send");
+            server.send_to(suffix.as_bytes(), peer).await.expect("send");
+        }
+    });
+    *udp = Some(udp::interact(address).await.expect("connect"));
+}
+
+#[when(expr = "I send {string} over UDP")]
+async fn when_i_send_over_udp(UdpWorld { udp, .. }: &mut UdpWorld, data: String) {
+    udp.as_mut()
+        .expect("udp")
+        .write_all(data.as_bytes())
+        .await
+        .expect("send");
+}
+
+#[when(expr = "I send an empty datagram over UDP")]
+async fn when_i_send_an_empty_datagram(UdpWorld { udp, .. }: &mut UdpWorld) {
+    udp.as_ref().expect("udp").send(&[]).await.expect("send");
+}
+
+#[when(regex = r"^I (read a chunk|read (\d+) bytes) over UDP$")]
+async fn when_i_read_over_udp(
+    UdpWorld { received, udp }: &mut UdpWorld,
+    action: String,
+    n: String,
+) {
+    let udp = udp.as_mut().expect("udp");
+    *received = Some(if action == "read a chunk" {
+        udp.read_chunk_bytes().await.expect("chunk")
+    } else {
+        udp.read_exact_n(n.parse().expect("usize"), None)
+            .await
+            .expect("read")
+    });
+}
+
+#[when(expr = "I receive a datagram")]
+async fn when_i_receive_a_datagram(UdpWorld { received, udp }: &mut UdpWorld) {
+    *received = Some(
+        udp.as_mut()
+            .expect("udp")
+            .recv_datagram()
+            .await
+            .expect("datagram"),
+    );
+}
+
+#[then(expr = "the data I received over UDP should equal {string}")]
+fn then_the_data_i_received_should_equal(
+    UdpWorld { received, .. }: &mut UdpWorld,
+    expected: String,
+) {
+    assert_eq!(Some(expected.into_bytes()), *received)
+}
+
+#[tokio::main]
+async fn main() {
+    UdpWorld::cucumber()
+        .fail_on_skipped()
+        .run_and_exit("./features/udp.feature")
+        .await
+}
@@ -0,0 +1,224 @@
+use cucumber::{World, given, then, when};
+use libspl::{
+    Interaction, PID,
+    interaction::unix::{self, Fifo, Listener, Unix},
+};
+use std::{fs, path::PathBuf, process, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{
+        UnixStream,
+        unix::pipe::{self, Receiver},
+    },
+    time,
+};
+
+#[derive(Debug, Default, World)]
+struct UnixWorld {
+    directory: PathBuf,
+    fifo: Option<Fifo>,
+    listener: Option<Listener>,
+    /// The other side's end of the FIFO interaction's output, and its path.
+    peer: Option<(PathBuf, Receiver)>,
+    unix: Option<Unix>,
+}
+impl UnixWorld {
+    /**
+    Resolves `name` inside this scenario's directory. `@` on its own becomes an abstract socket
+    name that is unique to this scenario.
+    */
+    fn path(&mut self, name: &str) -> PathBuf {
+        if self.directory.as_os_str().is_empty() {
+            self.directory = std::env::temp_dir().join(format!(
+                "libspl-unix-{}-{}",
+                process::id(),
+                rand::random::<u32>()
+            ));
+            fs::create_dir_all(&self.directory).expect("directory");
+        }
+        if name == "@" {
+            format!("@{}", self.directory.display()).into()
+        } else {
+            self.directory.join(name)
+        }
+    }
+}
+
+#[given(expr = "a Unix socket server at {string} that greets with {string}")]
+async fn given_a_unix_socket_server(world: &mut UnixWorld, name: String, greeting: String) {
+    let listener = Listener::bind(world.path(&name)).expect("bind");
+    tokio::spawn(async move {
+        loop {
+            let mut stream = listener.accept(None).await.expect("accept");
+            stream
+                .write_all(format!("{greeting}\n").as_bytes())
+                .await
+                .expect("write");
+        }
+    });
+}
+
+#[given(expr = "a Unix socket listener at {string}")]
+async fn given_a_unix_socket_listener(world: &mut UnixWorld, name: String) {
+    world.listener = Some(Listener::bind(world.path(&name)).expect("bind"));
+}
+
+#[given(expr = "the FIFOs {string} and {string}")]
+fn given_the_fifos(world: &mut UnixWorld, a: String, b: String) {
+    for name in [a, b] {
+        let status = process::Command::new("mkfifo")
+            .arg(world.path(&name))
+            .status()
+            .expect("mkfifo");
+        assert!(status.success());
+    }
+}
+
+#[when(expr = "I connect to the Unix socket at {string}")]
+async fn when_i_connect_to_the_unix_socket(world: &mut UnixWorld, name: String) {
+    world.unix = Some(unix::interact(world.path(&name)).await.expect("connect"));
+}
+
+#[when(expr = "a client connects to {string} and sends {string}")]
+async fn when_a_client_connects(world: &mut UnixWorld, name: String, data: String) {
+    let mut stream = UnixStream::connect(world.path(&name))
+        .await
+        .expect("connect");
+    stream
+        .write_all(format!("{data}\n").as_bytes())
+        .await
+        .expect("write");
+}
+
+#[when(expr = "I accept a Unix socket connection")]
+async fn when_i_accept_a_unix_socket_connection(world: &mut UnixWorld) {
+    world.unix = Some(
+        world
+            .listener
+            .as_ref()
+            .expect("listener")
+            .accept(Some(Duration::from_secs(1)))
+            .await
+            .expect("accept"),
+    );
+}
+
+#[when(
+    regex = r#"^I open a FIFO interaction reading from "(.*)" and writing to "(.*)"( before the other side opens it)?$"#
+)]
+async fn when_i_open_a_fifo_interaction(
+    world: &mut UnixWorld,
+    input: String,
+    output: String,
+    late: String,
+) {
+    let output = world.path(&output);
+    let peer = async {
+        if !late.is_empty() {
+            time::sleep(Duration::from_millis(100)).await;
+        }
+        pipe::OpenOptions::new().open_receiver(&output)
+    };
+    let (fifo, peer) = tokio::join!(Fifo::open(world.path(&input), &output), peer);
+    world.fifo = Some(fifo.expect("open"));
+    world.peer = Some((output, peer.expect("open")));
+}
+
+#[when(regex = r#"^the other side writes "(.*)" into "(.*)"(?: after (\d+) milliseconds)?$"#)]
+async fn when_the_other_side_writes(
+    world: &mut UnixWorld,
+    data: String,
+    name: String,
+    delay: String,
+) {
+    let path = world.path(&name);
+    let write = async move {
+        pipe::OpenOptions::new()
+            .open_sender(path)
+            .expect("open")
+            .write_all(format!("{data}\n").as_bytes())
+            .await
+            .expect("write");
+    };
+    if delay.is_empty() {
+        write.await
+    } else {
+        let delay = Duration::from_millis(delay.parse().expect("u64"));
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            write.await
+        });
+    }
+}
+
+#[when(expr = "I send the line {string} through the FIFO interaction")]
+async fn when_i_send_the_line_through_the_fifo(world: &mut UnixWorld, data: String) {
+    world
+        .fifo
+        .as_mut()
+        .expect("fifo")
+        .send_line(data.as_bytes())
+        .await
+        .expect("send");
+}
+
+#[then(expr = "the Unix interaction should receive the line {string}")]
+async fn then_the_unix_interaction_should_receive(
+    UnixWorld { fifo, unix, .. }: &mut UnixWorld,
+    line: String,
+) {
+    let deadline = Some(Duration::from_secs(1));
+    let received = match (unix, fifo) {
+        (Some(unix), _) => unix.read_line(deadline).await,
+        (None, Some(fifo)) => fifo.read_line(deadline).await,
+        (None, None) => panic!("no interaction"),
+    };
+    assert_eq!(format!("{line}\n").into_bytes(), received.expect("line"))
+}
+
+#[then(expr = "the FIFO interaction should reach the end of its input")]
+async fn then_the_fifo_interaction_should_reach_the_end(world: &mut UnixWorld) {
+    let fifo = world.fifo.as_mut().expect("fifo");
+    let read = time::timeout(Duration::from_secs(1), fifo.read(&mut [0; 1])).await;
+    assert_eq!(0, read.expect("timeout").expect("read"))
+}
+
+#[then(expr = "the Unix interaction's PID should be this process's")]
+async fn then_the_pid_should_be_this_processs(world: &mut UnixWorld) {
+    assert_eq!(
+        process::id(),
+        world
+            .unix
+            .as_ref()
+            .expect("unix")
+            .get_pid()
+            .await
+            .expect("PID")
+    )
+}
+
+#[then(expr = "the other side should read {string} from {string}")]
+async fn then_the_other_side_should_read(world: &mut UnixWorld, data: String, name: String) {
+    let expected = format!("{data}\n").into_bytes();
+    let mut received = vec![0; expected.len()];
+    let path = world.path(&name);
+    let (output, peer) = world.peer.as_mut().expect("peer");
+    assert_eq!(path, *output);
+    peer.read_exact(&mut received).await.expect("read");
+    assert_eq!(expected, received)
+}
+
+#[tokio::main]
+async fn main() {
+    UnixWorld::cucumber()
+        .fail_on_skipped()
+        .after(|_, _, _, _, world| {
+            Box::pin(async move {
+                if let Some(world) = world {
+                    let _ = fs::remove_dir_all(&world.directory);
+                }
+            })
+        })
+        .run_and_exit("./features/unix.feature")
+        .await
+}